@@ -0,0 +1,21 @@
+#!/bin/sh
+# Build the site into target/deploy, shrinking the wasm with wasm-opt
+# (from binaryen) when available.
+set -e
+# shown in "About this build" and error reports
+GIT_HASH=$(git rev-parse --short HEAD 2>/dev/null || true)
+export GIT_HASH
+cargo web deploy --release
+if [ -n "$GIT_HASH" ]; then
+    sed -i "s/<meta name=\"build\" content=\"dev\">/<meta name=\"build\" content=\"$GIT_HASH\">/" \
+        target/deploy/index.html
+fi
+WASM=target/deploy/asstosrt_wasm.wasm
+if command -v wasm-opt > /dev/null; then
+    before=$(wc -c < "$WASM")
+    wasm-opt -Oz --strip-debug -o "$WASM.opt" "$WASM"
+    mv "$WASM.opt" "$WASM"
+    echo "wasm-opt: $before -> $(wc -c < "$WASM") bytes"
+else
+    echo "wasm-opt not found, skipped" >&2
+fi
@@ -0,0 +1,189 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::borrow::Cow;
+
+const BUILTIN_EN: &[&str] = &[
+    "fuck",
+    "fucking",
+    "fucked",
+    "fucker",
+    "motherfucker",
+    "shit",
+    "shitty",
+    "bullshit",
+    "bitch",
+    "bastard",
+    "asshole",
+    "cunt",
+    "dick",
+    "dickhead",
+    "piss",
+    "pissed",
+    "damn",
+    "goddamn",
+    "crap",
+    "wanker",
+    "twat",
+    "slut",
+    "whore",
+];
+
+const BUILTIN_ZH: &[&str] = &[
+    "他妈的",
+    "他媽的",
+    "妈的",
+    "媽的",
+    "操你妈",
+    "操你媽",
+    "肏",
+    "傻逼",
+    "傻屄",
+    "牛逼",
+    "屌",
+    "婊子",
+    "王八蛋",
+    "混蛋",
+    "贱人",
+    "賤人",
+    "去死",
+];
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Mask {
+    /// "****"
+    Stars,
+    /// "f***"
+    KeepFirst,
+    /// "#$@&"
+    Grawlix,
+}
+
+pub struct Censor {
+    re: Regex,
+    mask: Mask,
+}
+
+fn builtin(lang: &str) -> Option<&'static [&'static str]> {
+    match lang {
+        "en" => Some(BUILTIN_EN),
+        "zh" => Some(BUILTIN_ZH),
+        _ => None,
+    }
+}
+
+impl Censor {
+    /// Build censor from names of built-in lists (e.g. "en", "zh") and
+    /// extra user words separated by comma or newline.
+    /// Return `None` if there is nothing to censor.
+    pub fn new(langs: &[String], words: &str, mask: Mask) -> Result<Option<Self>, &'static str> {
+        let mut list: Vec<&str> = Vec::new();
+        for lang in langs {
+            list.extend(builtin(lang).ok_or("unknown built-in word list")?);
+        }
+        list.extend(
+            words
+                .split(&[',', '\n'][..])
+                .map(|w| w.trim())
+                .filter(|w| !w.is_empty()),
+        );
+        if list.is_empty() {
+            return Ok(None);
+        }
+        list.sort_unstable();
+        list.dedup();
+        // prefer longer words when they overlap
+        list.sort_by_key(|w| std::cmp::Reverse(w.chars().count()));
+        let pattern = list
+            .into_iter()
+            .map(|w| {
+                // no word boundary in CJK text
+                if w.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    format!(r"\b{}\b", regex::escape(w))
+                } else {
+                    regex::escape(w)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        let re = Regex::new(&format!("(?i){}", pattern)).map_err(|_| "invalid censor word")?;
+        Ok(Some(Censor { re, mask }))
+    }
+
+    pub fn censor<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mask = self.mask;
+        self.re
+            .replace_all(text, |caps: &regex::Captures| mask.apply(&caps[0]))
+    }
+}
+
+impl Mask {
+    fn apply(self, word: &str) -> String {
+        match self {
+            Mask::Stars => word.chars().map(|_| '*').collect(),
+            Mask::KeepFirst => word
+                .chars()
+                .enumerate()
+                .map(|(i, c)| if i == 0 { c } else { '*' })
+                .collect(),
+            Mask::Grawlix => "#$@&%!"
+                .chars()
+                .cycle()
+                .take(word.chars().count())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn censor(langs: &[&str], words: &str, mask: Mask, text: &str) -> String {
+        let langs: Vec<String> = langs.iter().map(|l| l.to_string()).collect();
+        let censor = Censor::new(&langs, words, mask).unwrap().unwrap();
+        censor.censor(text).into_owned()
+    }
+
+    #[test]
+    fn test_masks() {
+        let text = "What the fuck? Damn.";
+        assert_eq!(
+            censor(&["en"], "", Mask::Stars, text),
+            "What the ****? ****."
+        );
+        assert_eq!(
+            censor(&["en"], "", Mask::KeepFirst, text),
+            "What the f***? D***."
+        );
+        assert_eq!(
+            censor(&["en"], "", Mask::Grawlix, text),
+            "What the #$@&? #$@&."
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        // longest match first, whole words only
+        assert_eq!(
+            censor(&["en"], "", Mask::Stars, "motherfucker, Dickens"),
+            "************, Dickens"
+        );
+        // no word boundary needed in CJK text
+        assert_eq!(censor(&["zh"], "", Mask::Stars, "你他妈的"), "你***");
+        assert_eq!(
+            censor(&[], "heck,\ndarn", Mask::KeepFirst, "Heck, darn it"),
+            "H***, d*** it"
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        assert!(Censor::new(&[], " , ", Mask::Stars).unwrap().is_none());
+        assert!(Censor::new(&["xx".into()], "", Mask::Stars).is_err());
+        // duplicates apart after sorting by length are removed too
+        let censor = Censor::new(&[], "darn,heck,darn", Mask::Stars)
+            .unwrap()
+            .unwrap();
+        assert_eq!(censor.re.as_str(), r"(?i)\bdarn\b|\bheck\b");
+    }
+}
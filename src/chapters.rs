@@ -0,0 +1,140 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+lazy_static! {
+    // "h:mm:ss.xxx", "mm:ss", "hh:mm:ss,xxx" etc.
+    static ref RE_TIME: Regex = Regex::new(
+        r"(?:(\d+):)?(\d{1,2}):(\d{2}(?:[.,]\d+)?)"
+    ).unwrap();
+}
+
+fn caps_to_secs(caps: &Captures) -> f32 {
+    let num = |i| {
+        caps.get(i)
+            .and_then(|n| n.as_str().replace(',', ".").parse().ok())
+            .unwrap_or(0f32)
+    };
+    num(1) * 3600.0 + num(2) * 60.0 + num(3)
+}
+
+/// Parse single "h:mm:ss.xxx" timestamp or seconds number.
+pub fn parse_time(s: &str) -> Option<f32> {
+    if let Ok(secs) = s.parse() {
+        return Some(secs);
+    }
+    RE_TIME
+        .captures(s)
+        .filter(|caps| caps[0].len() == s.len())
+        .map(|caps| caps_to_secs(&caps))
+}
+
+/// Parse timestamps from pasted text or a simple chapters file
+/// (OGM "CHAPTER01=00:10:00.000", "00:10:00 Title" lines, etc.).
+/// Lines containing only a number are treated as seconds.
+/// Return seconds in the original order.
+pub fn parse_timestamps(s: &str) -> Vec<f32> {
+    let mut points = Vec::new();
+    for line in s.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        if let Ok(secs) = line.parse::<f32>() {
+            points.push(secs);
+            continue;
+        }
+        points.extend(RE_TIME.captures_iter(line).map(|c| caps_to_secs(&c)));
+    }
+    points
+}
+
+/// Parse one optional timestamp per line, `None` for a blank line or
+/// one without a time, so that positions match the lines.
+pub fn parse_offsets(s: &str) -> Vec<Option<f32>> {
+    s.lines()
+        .map(|l| l.trim())
+        .map(|line| {
+            line.parse::<f32>()
+                .ok()
+                .or_else(|| RE_TIME.captures(line).map(|c| caps_to_secs(&c)))
+        })
+        .collect()
+}
+
+/// Like `parse_timestamps` but return sorted, deduplicated, positive
+/// seconds.
+pub fn parse_breakpoints(s: &str) -> Vec<f32> {
+    let mut points = parse_timestamps(s);
+    points.retain(|p| *p > 0.0);
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    points.dedup();
+    points
+}
+
+/// Turn breakpoints into consecutive (start, end) ranges covering the
+/// whole timeline.
+pub fn split_ranges(points: &[f32]) -> Vec<(Option<f32>, Option<f32>)> {
+    let starts = Some(None)
+        .into_iter()
+        .chain(points.iter().map(|p| Some(*p)));
+    let ends = points.iter().map(|p| Some(*p)).chain(Some(None));
+    starts.zip(ends).collect()
+}
+
+/// Limit (start, end) ranges to the `start` to `end` one, dropping the
+/// ones outside of it; `None` is unbounded.
+pub fn clip_ranges(
+    ranges: Vec<(Option<f32>, Option<f32>)>,
+    start: Option<f32>,
+    end: Option<f32>,
+) -> Vec<(Option<f32>, Option<f32>)> {
+    ranges
+        .into_iter()
+        .map(|(s, e)| {
+            let s = match (s, start) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            let e = match (e, end) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            (s, e)
+        })
+        .filter(|range| match range {
+            (Some(s), Some(e)) => s < e,
+            _ => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_offsets() {
+        assert_eq!(
+            parse_offsets("0:00:00\n\n  \n1:30.5\n90"),
+            [Some(0.0), None, None, Some(90.5), Some(90.0)]
+        );
+        assert_eq!(parse_offsets(""), []);
+    }
+
+    #[test]
+    fn test_clip_ranges() {
+        let ranges = split_ranges(&[600.0, 1200.0]);
+        assert_eq!(
+            clip_ranges(ranges.clone(), None, None),
+            [
+                (None, Some(600.0)),
+                (Some(600.0), Some(1200.0)),
+                (Some(1200.0), None)
+            ]
+        );
+        assert_eq!(
+            clip_ranges(ranges.clone(), Some(90.0), Some(900.0)),
+            [(Some(90.0), Some(600.0)), (Some(600.0), Some(900.0))]
+        );
+        assert_eq!(
+            clip_ranges(ranges, Some(1300.0), None),
+            [(Some(1300.0), None)]
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use crate::chapters::parse_time;
+
+/// Parse cut list of removed segments, one "start end [action]" per line
+/// (MPlayer EDL, seconds or "h:mm:ss.xxx"). Lines with a non-zero action
+/// (e.g. mute) are ignored.
+/// Return sorted, non-overlapping (start, end) secs.
+pub fn parse_edl(s: &str) -> Vec<(f32, f32)> {
+    let mut cuts: Vec<(f32, f32)> = s
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let start = parse_time(fields.next()?)?;
+            let end = parse_time(fields.next()?)?;
+            match fields.next() {
+                Some(action) if action != "0" => None,
+                _ if end > start => Some((start, end)),
+                _ => None,
+            }
+        })
+        .collect();
+    cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mut merged: Vec<(f32, f32)> = Vec::with_capacity(cuts.len());
+    for (start, end) in cuts {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Parse retiming table, one "source corrected" pair of times per line.
+/// Return (source, corrected) secs sorted by source, one per source.
+pub fn parse_anchors(s: &str) -> Vec<(f32, f32)> {
+    let mut anchors: Vec<(f32, f32)> = s
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = parse_time(fields.next()?)?;
+            let corrected = parse_time(fields.next()?)?;
+            // "nan" and "inf" parse as f32 too
+            Some((source, corrected)).filter(|_| source.is_finite() && corrected.is_finite())
+        })
+        .collect();
+    anchors.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    anchors.dedup_by(|a, b| a.0 == b.0);
+    anchors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_anchors() {
+        let anchors =
+            parse_anchors("# source corrected\n0:01:00 62.5\n10 11\n10 12\nnan 5\n20 inf\nbad\n");
+        assert_eq!(anchors, [(10.0, 11.0), (60.0, 62.5)]);
+    }
+}
@@ -1,2 +1,9 @@
+pub mod censor;
+pub mod chapters;
+pub mod edl;
+pub mod ocr;
+pub mod replace;
+pub mod series;
 pub mod subtitle;
+pub mod translit;
 pub mod zip;
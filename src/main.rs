@@ -5,12 +5,25 @@ use encoding::{
     label::encoding_from_whatwg_label,
     types::{DecoderTrap, EncoderTrap, EncodingRef},
 };
+use regex::Regex;
 use serde::Deserialize;
 use simplecc::Dict;
-use std::io::Cursor;
+use std::{borrow::Cow, io::Cursor};
 use stdweb::{web::ArrayBuffer, UnsafeTypedArray, Value};
 
-use asstosrt_wasm::{subtitle, zip::ZipWriter};
+use asstosrt_wasm::{
+    censor::{Censor, Mask},
+    chapters, edl,
+    ocr::OcrFix,
+    replace::Rules,
+    series,
+    subtitle::{
+        self, chapter::ChapterFormat, effect::Effects, format::Format, rtl::Bidi, wrap::LineBreak,
+        ConvertMeta, Rounding,
+    },
+    translit::{self, EntryNames},
+    zip::{self, Collision, ZipWriter},
+};
 
 macro_rules! throw {
     ( $e:expr ) => {
@@ -55,14 +68,110 @@ enum Lines {
 #[derive(Deserialize, Debug, Clone, Copy)]
 struct IgnoreCodecErr(bool);
 
+/// Line ending of output files, whatever the format writes.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+enum LineEnding {
+    Crlf,
+    Lf,
+}
+
+impl LineEnding {
+    fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            LineEnding::Crlf => {
+                let lone_lf = text
+                    .match_indices('\n')
+                    .any(|(i, _)| !text[..i].ends_with('\r'));
+                if lone_lf {
+                    Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+                } else {
+                    Cow::Borrowed(text)
+                }
+            }
+            LineEnding::Lf => Cow::Owned(text.replace("\r\n", "\n")),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 struct Options {
     in_charset: Option<Charset>,
     out_charset: Option<Charset>,
     lines: Lines,
     ignore_codec_err: IgnoreCodecErr,
+    /// write BOM into UTF-8 output, UTF-16 always has one
+    bom: bool,
+    line_ending: LineEnding,
     conv_dict: Option<String>,
     offset_secs: f32,
+    rounding: Rounding,
+    fps_from: f32,
+    fps_to: f32,
+    snap_fps: f32,
+    min_duration_secs: f32,
+    merge_duplicates: bool,
+    max_chars_per_line: usize,
+    max_lines: usize,
+    hard_breaks: LineBreak,
+    soft_breaks: LineBreak,
+    retime: String,
+    edl: String,
+    trim_start_secs: Option<f32>,
+    trim_end_secs: Option<f32>,
+    trim_rebase: bool,
+    keep_order: bool,
+    drop_signs: bool,
+    strip_sdh: bool,
+    /// write dialogue & signs into two files each
+    split_signs: bool,
+    /// set on the signs track of `split_signs`
+    #[serde(default)]
+    only_signs: bool,
+    forced: bool,
+    check_untranslated: bool,
+    check_coverage: bool,
+    max_cps: f32,
+    style_colors: String,
+    include_styles: String,
+    exclude_styles: String,
+    include_actors: String,
+    exclude_actors: String,
+    min_layer: Option<i32>,
+    max_layer: Option<i32>,
+    format: Format,
+    /// written next to `format`, into zip only
+    also_formats: Vec<Format>,
+    fps: f32,
+    vtt_styles: bool,
+    vtt_classes: bool,
+    vtt_regions: bool,
+    keep_basic_tags: bool,
+    keep_colors: bool,
+    speaker_sep: Option<String>,
+    speaker_upper: bool,
+    lrc_words: bool,
+    karaoke_lines: bool,
+    transcript_actors: bool,
+    comments: bool,
+    comment_cues: bool,
+    effects: Effects,
+    bidi: Bidi,
+    chapter_style: String,
+    chapter_regex: String,
+    chapter_comments: bool,
+    chapter_format: ChapterFormat,
+    extract_fonts: bool,
+    split_points: String,
+    collision: Collision,
+    entry_names: EntryNames,
+    series_folders: bool,
+    concat_offsets: String,
+    censor_langs: Vec<String>,
+    censor_words: String,
+    censor_mask: Mask,
+    ocr_langs: Vec<String>,
+    replace_rules: String,
+    replace_table: String,
 }
 js_deserializable!(Options);
 
@@ -92,80 +201,650 @@ impl Into<DecoderTrap> for IgnoreCodecErr {
     }
 }
 
-fn detect_charset(mut s: &[u8]) -> Option<EncodingRef> {
-    if s.len() > 4096 {
-        s = &s[..4096];
+/// Guess the charset of a file. Valid UTF-8 is taken as is; otherwise
+/// chardet looks at the events, whose text tells GB18030, Big5 and
+/// Shift_JIS apart far better than the mostly ASCII header.
+fn detect_charset(s: &[u8]) -> Option<EncodingRef> {
+    if std::str::from_utf8(s).is_ok() {
+        return Some(encoding::all::UTF_8);
+    }
+    let events = s.windows(8).position(|w| w == b"[Events]").unwrap_or(0);
+    let mut s = &s[events..];
+    if s.len() > 16384 {
+        s = &s[..16384];
     }
     let result = chardet::detect(s);
     log!(format!("chardet {:?}", result));
     encoding_from_whatwg_label(charset2encoding(&result.0))
 }
 
-fn convert(ass: ArrayBuffer, opts: Options) -> Box<[u8]> {
-    let ass: Vec<u8> = ass.into();
-    let in_charset = opts.in_charset.map_or_else(
-        || try_js!(detect_charset(&ass), "fail to detect ASS charset"),
-        |l| l.into(),
-    );
-    let out_charset = opts.out_charset.map_or(in_charset, |l| l.into());
-    let dict: Option<Dict> = opts.conv_dict.map(|s| Dict::load_str(&s));
-    let lines = opts.lines;
-    let mapper = |s: String| {
-        match lines {
-            Lines::First => s.lines().next(),
-            Lines::Last => s.lines().last(),
-            Lines::All => Some(s.as_str()),
+/// The first of comma separated charsets decoding `ass` without errors,
+/// or the one with the fewest of them.
+fn pick_charset(ass: &[u8], labels: &str) -> EncodingRef {
+    let mut best: Option<(EncodingRef, usize)> = None;
+    for label in labels.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+        let charset: EncodingRef = Charset(label.into()).into();
+        let errors = charset
+            .decode(ass, DecoderTrap::Replace)
+            .map_or(usize::MAX, |s| s.matches('\u{fffd}').count());
+        if errors == 0 {
+            return charset;
         }
-        .map(|s| dict.as_ref().map_or(s.into(), |d| d.replace_all(s)))
-    };
+        log!(format!("{}: {} decoding errors", label, errors));
+        if best.map_or(true, |(_, n)| errors < n) {
+            best = Some((charset, errors));
+        }
+    }
+    try_js!(best, "unknown charset name").0
+}
 
-    let ass = try_js!(
-        in_charset.decode(&ass, opts.ignore_codec_err.into()),
-        "fail to decode",
-        err
-    );
-    let srt = try_js!(subtitle::ass_to_srt(
-        &ass,
-        true,
-        Some(mapper),
-        opts.offset_secs
-    ));
+fn in_charset(ass: &[u8], opts: &Options) -> EncodingRef {
+    opts.in_charset.as_ref().map_or_else(
+        || try_js!(detect_charset(ass), "fail to detect ASS charset"),
+        |l| pick_charset(ass, &l.0),
+    )
+}
+
+/// Charset for output in `format`, the input one if not chosen.
+fn output_charset(in_charset: EncodingRef, format: Format, opts: &Options) -> EncodingRef {
+    match format {
+        // WebVTT and JSON must be UTF-8, TTML declares it, and clean ASS
+        // is for re-encoding to it
+        Format::WebVtt | Format::Json | Format::Ttml | Format::Ass => encoding::all::UTF_8,
+        _ => opts.out_charset.clone().map_or(in_charset, |l| l.into()),
+    }
+}
+
+/// Byte offset of the first sequence `charset` can't decode.
+fn decode_error_at(ass: &[u8], charset: EncodingRef) -> Option<usize> {
+    let mut decoder = charset.raw_decoder();
+    let mut sink = String::new();
+    match decoder.raw_feed(ass, &mut sink) {
+        (at, Some(_)) => Some(at),
+        // truncated at the end
+        _ => decoder.raw_finish(&mut sink).map(|_| ass.len()),
+    }
+}
+
+/// Decode input, return the text, its charset and the one for output.
+/// Unless ignoring encoding errors, fail at the first invalid byte.
+fn try_decode(ass: &[u8], opts: &Options) -> Result<(String, EncodingRef, EncodingRef), String> {
+    let in_charset = in_charset(ass, opts);
+    let out_charset = output_charset(in_charset, opts.format, opts);
+    let text = in_charset
+        .decode(ass, opts.ignore_codec_err.into())
+        .map_err(|e| {
+            let at = decode_error_at(ass, in_charset)
+                .map_or_else(String::new, |at| format!(" at byte {}", at));
+            format!(
+                "fail to decode as {}: {}{}",
+                charset_name(in_charset),
+                e,
+                at
+            )
+        })?;
+    Ok((text, in_charset, out_charset))
+}
 
+fn decode(ass: &[u8], opts: &Options) -> (String, EncodingRef, EncodingRef) {
+    try_js!(try_decode(ass, opts))
+}
+
+/// Count input chars replaced by lossy decoding into `meta`.
+fn report_decode_errors(texts: &[&str], opts: &Options, meta: &mut ConvertMeta) {
+    if !opts.ignore_codec_err.0 {
+        return;
+    }
+    meta.decode_errors = texts.iter().map(|t| t.matches('\u{fffd}').count()).sum();
+    if meta.decode_errors > 0 {
+        meta.warnings.push(format!(
+            "{} undecodable characters replaced by \u{fffd}",
+            meta.decode_errors
+        ));
+    }
+}
+
+/// Name of a charset for reports, e.g. "gb18030".
+fn charset_name(charset: EncodingRef) -> &'static str {
+    charset.whatwg_name().unwrap_or_else(|| charset.name())
+}
+
+/// Count chars of `text` that `charset` can't encode.
+fn unencodable(text: &str, charset: EncodingRef) -> usize {
+    let mut encoder = charset.raw_encoder();
+    let mut sink = Vec::new();
+    let mut rest = text;
+    let mut count = 0;
+    while let (_, Some(err)) = encoder.raw_feed(rest, &mut sink) {
+        count += 1;
+        rest = &rest[err.upto as usize..];
+        sink.clear();
+    }
+    count
+}
+
+/// Encode output, reporting chars replaced by `ignore_codec_err` in `meta`.
+fn encode(srt: &str, charset: EncodingRef, opts: &Options, meta: &mut ConvertMeta) -> Box<[u8]> {
+    let srt = &opts.line_ending.apply(srt);
+    if let Some(chapters) = meta.chapters.as_mut() {
+        *chapters = opts.line_ending.apply(chapters).into_owned();
+    }
+    let name = charset.whatwg_name().unwrap_or("");
+    let is_utf = name.starts_with("utf-");
+    if opts.ignore_codec_err.0 && !is_utf {
+        meta.lossy_chars = unencodable(srt, charset);
+        if meta.lossy_chars > 0 {
+            meta.warnings.push(format!(
+                "{} characters not in {}, replaced",
+                meta.lossy_chars,
+                charset.name()
+            ));
+        }
+    }
     let mut output = Vec::new();
-    // insert BOM for utf-16
-    if out_charset
-        .whatwg_name()
-        .map_or(false, |n| n.starts_with("utf-16"))
-    {
-        try_js!(out_charset.encode_to("\u{feff}", EncoderTrap::Strict, &mut output));
+    // insert BOM for utf-16, and utf-8 if asked
+    if name.starts_with("utf-16") || opts.bom && name == "utf-8" {
+        try_js!(charset.encode_to("\u{feff}", EncoderTrap::Strict, &mut output));
     }
 
     try_js!(
-        out_charset.encode_to(&srt, opts.ignore_codec_err.into(), &mut output),
+        charset.encode_to(srt, opts.ignore_codec_err.into(), &mut output),
         "fail to encode",
         err
     );
     output.into_boxed_slice()
 }
 
+/// Text transforms applied to each dialogue.
+fn mapper(opts: &Options) -> impl FnMut(String) -> Option<String> + '_ {
+    let dict: Option<Dict> = opts.conv_dict.as_ref().map(|s| Dict::load_str(s));
+    let ocr = try_js!(OcrFix::new(&opts.ocr_langs));
+    let rules = try_js!(Rules::new(&opts.replace_rules, &opts.replace_table));
+    let censor = try_js!(Censor::new(
+        &opts.censor_langs,
+        &opts.censor_words,
+        opts.censor_mask
+    ));
+    move |s: String| {
+        match opts.lines {
+            Lines::First => s.lines().next(),
+            Lines::Last => s.lines().last(),
+            Lines::All => Some(s.as_str()),
+        }
+        .map(|s| ocr.as_ref().map_or(s.into(), |o| o.fix(s)))
+        .map(|s| match dict {
+            Some(ref d) => d.replace_all(&s),
+            None => s.into_owned(),
+        })
+        .map(|s| match rules {
+            Some(ref r) => r.apply(&s).into_owned(),
+            None => s,
+        })
+        .map(|s| match censor {
+            Some(ref c) => c.censor(&s).into_owned(),
+            None => s,
+        })
+    }
+}
+
+fn sub_opts(opts: &Options) -> subtitle::Options {
+    subtitle::Options {
+        no_effect: true,
+        effects: opts.effects,
+        bidi: opts.bidi,
+        anchors: edl::parse_anchors(&opts.retime),
+        offset_secs: opts.offset_secs,
+        rounding: opts.rounding,
+        fps_from: opts.fps_from,
+        fps_to: opts.fps_to,
+        snap_fps: opts.snap_fps,
+        min_duration_secs: opts.min_duration_secs,
+        merge_duplicates: opts.merge_duplicates,
+        max_chars_per_line: opts.max_chars_per_line,
+        max_lines: opts.max_lines,
+        hard_breaks: opts.hard_breaks,
+        soft_breaks: opts.soft_breaks,
+        cuts: edl::parse_edl(&opts.edl),
+        trim_start_secs: opts.trim_start_secs,
+        trim_end_secs: opts.trim_end_secs,
+        trim_rebase: opts.trim_rebase,
+        keep_order: opts.keep_order,
+        drop_signs: opts.drop_signs,
+        strip_sdh: opts.strip_sdh,
+        only_signs: opts.only_signs,
+        forced: opts.forced,
+        check_untranslated: opts.check_untranslated,
+        check_coverage: opts.check_coverage,
+        max_cps: opts.max_cps,
+        style_colors: subtitle::style::parse_style_colors(&opts.style_colors),
+        include_styles: subtitle::filter::parse_patterns(&opts.include_styles),
+        exclude_styles: subtitle::filter::parse_patterns(&opts.exclude_styles),
+        include_actors: subtitle::filter::parse_patterns(&opts.include_actors),
+        exclude_actors: subtitle::filter::parse_patterns(&opts.exclude_actors),
+        min_layer: opts.min_layer,
+        max_layer: opts.max_layer,
+        format: opts.format,
+        fps: opts.fps,
+        vtt_styles: opts.vtt_styles,
+        vtt_classes: opts.vtt_classes,
+        vtt_regions: opts.vtt_regions,
+        keep_basic_tags: opts.keep_basic_tags,
+        keep_colors: opts.keep_colors,
+        speaker_sep: opts.speaker_sep.clone(),
+        speaker_upper: opts.speaker_upper,
+        lrc_words: opts.lrc_words,
+        karaoke_lines: opts.karaoke_lines,
+        transcript_actors: opts.transcript_actors,
+        comments: opts.comments,
+        comment_cues: opts.comment_cues,
+        chapter_style: Some(opts.chapter_style.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_owned()),
+        chapter_regex: Some(opts.chapter_regex.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| try_js!(Regex::new(s), "invalid chapter pattern", err)),
+        chapter_comments: opts.chapter_comments,
+        chapter_format: opts.chapter_format,
+    }
+}
+
+/// Convert one decoded ASS file, return one SRT per split part.
+fn convert_str(ass: &str, opts: &Options) -> Vec<(String, ConvertMeta)> {
+    let mut mapper = mapper(opts);
+    let points = chapters::parse_breakpoints(&opts.split_points);
+    let ranges = if points.is_empty() {
+        vec![(opts.trim_start_secs, opts.trim_end_secs)]
+    } else {
+        // parts within the trim range
+        let ranges = chapters::split_ranges(&points);
+        chapters::clip_ranges(ranges, opts.trim_start_secs, opts.trim_end_secs)
+    };
+    if ranges.is_empty() {
+        throw!("no split part within the trim range");
+    }
+    ranges
+        .into_iter()
+        .map(|(trim_start_secs, trim_end_secs)| {
+            let sub_opts = subtitle::Options {
+                trim_start_secs,
+                trim_end_secs,
+                trim_rebase: opts.trim_rebase || !points.is_empty(),
+                ..sub_opts(opts)
+            };
+            try_js!(subtitle::convert(ass, &sub_opts, Some(&mut mapper)))
+        })
+        .collect()
+}
+
+/// Convert one decoded ASS file, return one encoded SRT per split part,
+/// in the output charset for `opts.format`.
+fn convert(ass: &str, in_charset: EncodingRef, opts: &Options) -> Vec<(Box<[u8]>, ConvertMeta)> {
+    let out_charset = output_charset(in_charset, opts.format, opts);
+    convert_str(ass, opts)
+        .into_iter()
+        .map(|(srt, mut meta)| {
+            meta.charset = Some(charset_name(in_charset));
+            report_decode_errors(&[ass], opts, &mut meta);
+            (encode(&srt, out_charset, opts, &mut meta), meta)
+        })
+        .collect()
+}
+
+/// "a.srt" -> "a.part2.srt"
+fn part_name(fname: &str, n: usize) -> String {
+    match fname.rfind('.') {
+        Some(i) => format!("{}.part{}{}", &fname[..i], n, &fname[i..]),
+        None => format!("{}.part{}", fname, n),
+    }
+}
+
+/// "a.srt" -> "a.vtt"
+fn format_name(fname: &str, format: Format) -> String {
+    let stem = fname.rfind('.').map_or(fname, |i| &fname[..i]);
+    format!("{}.{}", stem, format.extension())
+}
+
+/// "a.srt" -> "a.signs.srt"
+fn track_name(fname: &str, track: &str) -> String {
+    match fname.rfind('.') {
+        Some(i) => format!("{}.{}{}", &fname[..i], track, &fname[i..]),
+        None => format!("{}.{}", fname, track),
+    }
+}
+
+/// "a.zh.srt" -> "a.zh.forced.srt", as Plex & Kodi name forced tracks
+fn forced_name(fname: &str) -> String {
+    match fname.rfind('.') {
+        Some(i) => format!("{}.forced{}", &fname[..i], &fname[i..]),
+        None => format!("{}.forced", fname),
+    }
+}
+
+/// "a.srt" -> "a.chapters.txt"
+fn chapters_name(fname: &str) -> String {
+    let stem = fname.rfind('.').map_or(fname, |i| &fname[..i]);
+    format!("{}.chapters.txt", stem)
+}
+
+/// Name each part after the file, return (name, part).
+fn name_parts<T>(fname: String, mut parts: Vec<T>) -> Vec<(String, T)> {
+    if parts.len() == 1 {
+        return vec![(fname, parts.remove(0))];
+    }
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, part)| (part_name(&fname, i + 1), part))
+        .collect()
+}
+
+/// Statistics of one output for the batch summary;
+/// `name` is filled by worker if `None`.
+fn stats(name: Option<&str>, meta: &ConvertMeta) -> Value {
+    js!(return {
+        name: @{name},
+        dialogues: @{meta.dialogues as u32},
+        duration: @{meta.duration_secs},
+        script: @{meta.script},
+        forced: @{meta.forced},
+        blocks: @{&meta.blocks},
+        drawings: @{meta.drawings as u32},
+        fast_cues: @{meta.fast_cues as u32},
+        max_cps: @{meta.max_cps},
+        lossy_chars: @{meta.lossy_chars as u32},
+        charset: @{meta.charset},
+        decode_errors: @{meta.decode_errors as u32},
+        warnings: @{&meta.warnings},
+    })
+}
+
+/// Return `{blob, warnings, chapters, stats}` to JS.
+fn output(data: &[u8], mime: &str, meta: ConvertMeta, stats: Vec<Value>) -> Value {
+    let data = unsafe { UnsafeTypedArray::new(data) };
+    js!(return {
+        blob: new Blob([@{data}], {type: @{mime}}),
+        warnings: @{meta.warnings},
+        chapters: @{meta.chapters},
+        stats: @{stats},
+    })
+}
+
 fn ass_to_srt(ass: ArrayBuffer, opts: Options) -> Value {
-    let output = convert(ass, opts);
-    let output = unsafe { UnsafeTypedArray::new(&output) };
-    js! (return new Blob([@{output}], {type: "text/srt"}))
+    let ass: Vec<u8> = ass.into();
+    let (ass, in_charset, _) = decode(&ass, &opts);
+    let mut parts = convert(&ass, in_charset, &opts);
+    if parts.len() > 1 {
+        throw!("split output must be archived into zip");
+    }
+    let (output_data, meta) = parts.remove(0);
+    let stats = vec![stats(None, &meta)];
+    output(&output_data, opts.format.mime(), meta, stats)
 }
 
 fn ass_to_srt_bulk(files: Vec<ArrayBuffer>, filenames: Vec<String>, opts: Options) -> Value {
+    let mut entries = Vec::new();
+    let mut fonts: Vec<(String, Box<[u8]>)> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut all_stats = Vec::new();
+    for (fname, f) in filenames.into_iter().zip(files.into_iter()) {
+        let bytes: Vec<u8> = f.into();
+        // a file not in the charset fails alone, not the whole batch
+        let (ass, in_charset, _) = match try_decode(&bytes, &opts) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                warnings.push(format!("{}: {}", fname, e));
+                continue;
+            }
+        };
+        if opts.extract_fonts {
+            for (name, data) in subtitle::fonts::extract_fonts(&ass) {
+                // the same font is often attached to every episode
+                let name = format!("fonts/{}", name);
+                if fonts.iter().all(|(n, _)| *n != name) {
+                    fonts.push((name, data.into()));
+                }
+            }
+        }
+        let tracks = if opts.split_signs {
+            vec![
+                (
+                    track_name(&fname, "dialogue"),
+                    Options {
+                        drop_signs: true,
+                        ..opts.clone()
+                    },
+                ),
+                (
+                    track_name(&fname, "signs"),
+                    Options {
+                        drop_signs: false,
+                        only_signs: true,
+                        ..opts.clone()
+                    },
+                ),
+            ]
+        } else {
+            vec![(fname, opts.clone())]
+        };
+        for (fname, opts) in tracks {
+            let parts = convert(&ass, in_charset, &opts);
+            for (fname, (srt, meta)) in name_parts(fname.clone(), parts) {
+                let fname = if meta.forced {
+                    forced_name(&fname)
+                } else {
+                    fname
+                };
+                warnings.extend(meta.warnings.iter().map(|w| format!("{}: {}", fname, w)));
+                all_stats.push(stats(Some(&fname), &meta));
+                if let Some(chapters) = meta.chapters {
+                    entries.push((chapters_name(&fname), chapters.into_bytes().into()));
+                }
+                entries.push((fname, srt));
+            }
+            // warnings, stats & chapters are the same as the main format's
+            for &format in opts.also_formats.iter().filter(|&&f| f != opts.format) {
+                let format_opts = Options {
+                    format,
+                    ..opts.clone()
+                };
+                let parts = convert(&ass, in_charset, &format_opts);
+                for (fname, (data, meta)) in name_parts(format_name(&fname, format), parts) {
+                    let fname = if meta.forced {
+                        forced_name(&fname)
+                    } else {
+                        fname
+                    };
+                    entries.push((fname, data));
+                }
+            }
+        }
+    }
+    if opts.series_folders {
+        for (fname, _) in entries.iter_mut() {
+            *fname = series::organize(fname);
+        }
+    }
+    entries.extend(fonts);
+    let entries = entries
+        .into_iter()
+        .map(|(fname, f)| (translit::transliterate(&fname, opts.entry_names), f))
+        .collect();
     let mut buf = Cursor::new(Vec::new());
     {
         let mut zip = ZipWriter::new(&mut buf);
-        filenames
-            .into_iter()
-            .zip(files.into_iter().map(|f| convert(f, opts.clone())))
-            .for_each(|(fname, f)| try_js!(zip.write_file(&fname, &f[..])));
+        for (fname, f) in zip::resolve_names(entries, opts.collision) {
+            try_js!(zip.write_file(&fname, &f[..]));
+        }
         try_js!(zip.close());
     }
-    let output = unsafe { UnsafeTypedArray::new(buf.get_ref()) };
-    js! (return new Blob([@{output}], {type: "application/zip"}))
+    let meta = ConvertMeta {
+        warnings,
+        ..Default::default()
+    };
+    output(buf.get_ref(), "application/zip", meta, all_stats)
+}
+
+/// Put all outputs into one UTF-8 text, separated by file names,
+/// with the chosen line endings.
+fn ass_to_srt_merged(files: Vec<ArrayBuffer>, filenames: Vec<String>, opts: Options) -> Value {
+    let mut text = String::new();
+    let mut warnings = Vec::new();
+    let mut all_stats = Vec::new();
+    for (fname, f) in filenames.into_iter().zip(files.into_iter()) {
+        let bytes: Vec<u8> = f.into();
+        // a file not in the charset fails alone, not the whole batch
+        let ass = match try_decode(&bytes, &opts) {
+            Ok((ass, _, _)) => ass,
+            Err(e) => {
+                warnings.push(format!("{}: {}", fname, e));
+                continue;
+            }
+        };
+        let parts = convert_str(&ass, &opts);
+        for (fname, (srt, meta)) in name_parts(fname, parts) {
+            warnings.extend(meta.warnings.iter().map(|w| format!("{}: {}", fname, w)));
+            all_stats.push(stats(Some(&fname), &meta));
+            text.push_str(&format!("--- {} ---\r\n{}", fname, srt));
+        }
+    }
+    let text = opts.line_ending.apply(&text);
+    let meta = ConvertMeta {
+        warnings,
+        ..Default::default()
+    };
+    output(text.as_bytes(), "text/plain", meta, all_stats)
+}
+
+/// Convert without output, return `{dialogues, warnings, stats}`.
+fn validate_subtitle(ass: ArrayBuffer, opts: Options) -> Value {
+    let ass: Vec<u8> = ass.into();
+    let (ass, in_charset, _) = decode(&ass, &opts);
+    // encode as well to catch characters not in output charset
+    let parts = convert(&ass, in_charset, &opts);
+    let dialogues: usize = parts.iter().map(|(_, meta)| meta.dialogues).sum();
+    let all_stats: Vec<_> = parts.iter().map(|(_, meta)| stats(None, meta)).collect();
+    let warnings: Vec<String> = parts
+        .into_iter()
+        .flat_map(|(_, meta)| meta.warnings)
+        .collect();
+    js!(return {
+        dialogues: @{dialogues as u32},
+        warnings: @{warnings},
+        stats: @{all_stats},
+    })
+}
+
+/// Compare two files, return a text report and its summary.
+fn compare_subtitles(a: ArrayBuffer, b: ArrayBuffer, opts: Options) -> Value {
+    let a: Vec<u8> = a.into();
+    let b: Vec<u8> = b.into();
+    let (a, _, _) = decode(&a, &opts);
+    let (b, _, _) = decode(&b, &opts);
+    let (report, summary) = try_js!(subtitle::diff::compare(
+        &a,
+        &b,
+        &sub_opts(&opts),
+        Some(mapper(&opts))
+    ));
+    let summary = format!(
+        "{} added, {} removed, {} changed",
+        summary.added, summary.removed, summary.changed
+    );
+    js!(return {
+        blob: new Blob([@{report}], {type: "text/plain"}),
+        summary: @{summary},
+    })
+}
+
+/// Join files end to end into single SRT.
+fn ass_to_srt_concat(files: Vec<ArrayBuffer>, opts: Options) -> Value {
+    let mut charsets = None;
+    let scripts: Vec<String> = files
+        .into_iter()
+        .map(|f| {
+            let ass: Vec<u8> = f.into();
+            let (ass, in_charset, out_charset) = decode(&ass, &opts);
+            charsets.get_or_insert((in_charset, out_charset));
+            ass
+        })
+        .collect();
+    let scripts: Vec<&str> = scripts.iter().map(|s| s.as_str()).collect();
+    let offsets = chapters::parse_offsets(&opts.concat_offsets);
+    let (srt, mut meta) = try_js!(subtitle::concat_to_srt(
+        &scripts,
+        &offsets,
+        &sub_opts(&opts),
+        Some(mapper(&opts))
+    ));
+    let (in_charset, out_charset) = try_js!(charsets, "no file to join");
+    meta.charset = Some(charset_name(in_charset));
+    report_decode_errors(&scripts, &opts, &mut meta);
+    let srt = encode(&srt, out_charset, &opts, &mut meta);
+    let stats = vec![stats(None, &meta)];
+    output(&srt, opts.format.mime(), meta, stats)
+}
+
+/// Merge two files into one bilingual SRT, the first on top.
+fn ass_to_srt_bilingual(files: Vec<ArrayBuffer>, opts: Options) -> Value {
+    if files.len() != 2 {
+        throw!("select exactly two files to merge");
+    }
+    let mut charsets = None;
+    let scripts: Vec<String> = files
+        .into_iter()
+        .map(|f| {
+            let ass: Vec<u8> = f.into();
+            let (ass, in_charset, out_charset) = decode(&ass, &opts);
+            charsets.get_or_insert((in_charset, out_charset));
+            ass
+        })
+        .collect();
+    let (srt, mut meta) = try_js!(subtitle::bilingual::merge(
+        &scripts[0],
+        &scripts[1],
+        &sub_opts(&opts),
+        Some(mapper(&opts))
+    ));
+    let (in_charset, out_charset) = charsets.unwrap();
+    meta.charset = Some(charset_name(in_charset));
+    report_decode_errors(&[&scripts[0], &scripts[1]], &opts, &mut meta);
+    let srt = encode(&srt, out_charset, &opts, &mut meta);
+    let stats = vec![stats(None, &meta)];
+    output(&srt, opts.format.mime(), meta, stats)
+}
+
+/// Cues of one file as `[{start, end, overlap, short}]` for the timeline.
+fn timeline(ass: ArrayBuffer, opts: Options) -> Value {
+    let ass: Vec<u8> = ass.into();
+    let (ass, _, _) = decode(&ass, &opts);
+    let cues = try_js!(subtitle::timeline::timeline(&ass, &sub_opts(&opts)));
+    let cues: Vec<Value> = cues
+        .iter()
+        .map(|c| {
+            js!(return {
+                start: @{c.start},
+                end: @{c.end},
+                overlap: @{c.overlap},
+                short: @{c.short},
+            })
+        })
+        .collect();
+    js!(return @{cues})
+}
+
+/// `{version, commit, profile}` of this build; commit is from `GIT_HASH`
+/// set at build time by the deploy script.
+fn version() -> Value {
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    js!(return {
+        version: @{env!("CARGO_PKG_VERSION")},
+        commit: @{option_env!("GIT_HASH")},
+        profile: @{profile},
+    })
 }
 
 fn main() {
@@ -173,5 +852,12 @@ fn main() {
     js! {
         Module.exports.assToSrt = @{ass_to_srt};
         Module.exports.assToSrtBulk = @{ass_to_srt_bulk};
+        Module.exports.assToSrtConcat = @{ass_to_srt_concat};
+        Module.exports.assToSrtBilingual = @{ass_to_srt_bilingual};
+        Module.exports.assToSrtMerged = @{ass_to_srt_merged};
+        Module.exports.compareSubtitles = @{compare_subtitles};
+        Module.exports.validateSubtitle = @{validate_subtitle};
+        Module.exports.timeline = @{timeline};
+        Module.exports.version = @{version};
     }
 }
@@ -0,0 +1,154 @@
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+
+type Fix = fn(&Captures) -> String;
+
+/// (pattern, replacement) for any Latin-script text.
+const COMMON: &[(&str, Fix)] = &[
+    // he||o, wi|| -> hello, will
+    (r"(\p{Ll})(\|+)", |c| {
+        format!("{}{}", &c[1], "l".repeat(c[2].len()))
+    }),
+    // |t's -> It's
+    (r"\|(\p{Ll})", |c| format!("I{}", &c[1])),
+    // box edges read as pipes at line start/end, after the ones in words
+    (r"(?m)^\|[ \t]*|[ \t]*\|(\r?)$", |c| {
+        c.get(1).map_or("", |m| m.as_str()).to_owned()
+    }),
+    // g0od, C0LD -> good, COLD
+    (r"(\p{L})0(\p{L})", |c| {
+        let o = if c[1].chars().all(char::is_lowercase) {
+            'o'
+        } else {
+            'O'
+        };
+        format!("{}{}{}", &c[1], o, &c[2])
+    }),
+    // 2O15 -> 2015
+    (r"(\d)[Oo](\d)", |c| format!("{}0{}", &c[1], &c[2])),
+    // backtick or acute accent as apostrophe
+    (r"(\p{L})[`´](\p{L})", |c| format!("{}'{}", &c[1], &c[2])),
+];
+
+const EN: &[(&str, Fix)] = &[
+    // wiII, heIIo -> will, hello; only in words starting lowercase, as
+    // names like McIntosh have a capital I after lowercase letters
+    (r"\b(\p{Ll}+)(I+)(\p{Ll}*)\b", |c| {
+        format!("{}{}{}", &c[1], "l".repeat(c[2].len()), &c[3])
+    }),
+    // StiII -> Still, a doubled I only
+    (r"\b(\p{Lu}\p{Ll}+)(II+)(\p{Ll}*)\b", |c| {
+        format!("{}{}{}", &c[1], "l".repeat(c[2].len()), &c[3])
+    }),
+    // l'm, l'll -> I'm, I'll
+    (r"\bl'(m|ll|ve|d)\b", |c| format!("I'{}", &c[1])),
+    // lone "l" -> "I"
+    (r#"(^|[\s"(\-])l([\s,.!?]|$)"#, |c| {
+        format!("{}I{}", &c[1], &c[2])
+    }),
+    // no English word starts with "rn": rnake -> make
+    (r"\brn([aeiou])", |c| format!("m{}", &c[1])),
+    // don 't, don' t -> don't
+    (r"(\p{L})(?: ' ?|' )(s|t|re|ve|ll|d|m)\b", |c| {
+        format!("{}'{}", &c[1], &c[2])
+    }),
+];
+
+fn builtin(lang: &str) -> Option<&'static [(&'static str, Fix)]> {
+    match lang {
+        "en" => Some(EN),
+        _ => None,
+    }
+}
+
+/// Fixes of common OCR misreads in subtitles ripped from images.
+pub struct OcrFix {
+    rules: Vec<(Regex, Fix)>,
+}
+
+impl OcrFix {
+    /// Build from names of language rule sets (e.g. "en"), rules for
+    /// Latin script are always included. Return `None` if no language.
+    pub fn new(langs: &[String]) -> Result<Option<Self>, &'static str> {
+        if langs.is_empty() {
+            return Ok(None);
+        }
+        let mut rules = COMMON.to_vec();
+        for lang in langs.iter().filter(|l| *l != "common") {
+            rules.extend(builtin(lang).ok_or("unknown OCR rule set")?);
+        }
+        let rules = rules
+            .into_iter()
+            .map(|(re, fix)| (Regex::new(re).unwrap(), fix))
+            .collect();
+        Ok(Some(OcrFix { rules }))
+    }
+
+    pub fn fix<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for (re, fix) in self.rules.iter() {
+            if let Cow::Owned(fixed) = re.replace_all(&text, |c: &Captures| fix(c)) {
+                text = Cow::Owned(fixed);
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(lang: &str, text: &str) -> String {
+        let ocr = OcrFix::new(&[lang.to_owned()]).unwrap().unwrap();
+        ocr.fix(text).into_owned()
+    }
+
+    #[test]
+    fn test_common() {
+        assert_eq!(fix("common", "|t's he||o, wi||"), "It's hello, will");
+        assert_eq!(
+            fix("common", "| Hello there |\r\n| Bye"),
+            "Hello there\r\nBye"
+        );
+        assert_eq!(fix("common", "g0od, C0LD 2O15"), "good, COLD 2015");
+        assert_eq!(fix("common", "don`t, it´s"), "don't, it's");
+        // left alone
+        for text in &["R2D2 at 10:00, 0.5 kg of H2O", "Hello, Bill", "a 'quote'"] {
+            assert_eq!(fix("common", text), *text);
+        }
+    }
+
+    #[test]
+    fn test_en() {
+        assert_eq!(
+            fix("en", "wiII you? heIIo. StiII, aII"),
+            "will you? hello. Still, all"
+        );
+        assert_eq!(
+            fix("en", "l'm sure l'll go.\nl think so"),
+            "I'm sure I'll go.\nI think so"
+        );
+        assert_eq!(fix("en", "\"l, robot\" - l"), "\"I, robot\" - I");
+        assert_eq!(fix("en", "rnake it"), "make it");
+        assert_eq!(fix("en", "don 't, can' t"), "don't, can't");
+        // names, acronyms and plain words left alone
+        for text in &[
+            "McIntosh, MacInnes and DiIorio",
+            "iPhone, CIA, Ill at ease",
+            "turn right, learn it",
+            "the boys' toys",
+            "lol, all well",
+        ] {
+            assert_eq!(fix("en", text), *text);
+        }
+    }
+
+    #[test]
+    fn test_rule_sets() {
+        assert!(OcrFix::new(&[]).unwrap().is_none());
+        assert!(OcrFix::new(&["xx".to_owned()]).is_err());
+        // common rules come with any language
+        assert_eq!(fix("en", "he||o"), "hello");
+    }
+}
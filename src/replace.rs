@@ -0,0 +1,111 @@
+use regex::{Captures, Regex};
+use std::{borrow::Cow, collections::HashMap};
+
+/// User's find & replace rules on dialogue text, applied in order, then
+/// the replacement table.
+pub struct Rules {
+    rules: Vec<(Regex, String)>,
+    table: Option<Table>,
+}
+
+/// Any of the terms, and term -> replacement.
+type Table = (Regex, HashMap<String, String>);
+
+/// Parse "from<TAB>to" lines into a regex matching any term, longer ones
+/// first, and the replacements. Fail if there are too many terms for
+/// the regex size limit.
+fn parse_table(s: &str) -> Result<Option<Table>, &'static str> {
+    let table: HashMap<String, String> = s
+        .lines()
+        .filter_map(|line| {
+            let (from, to) = line.split_once('\t')?;
+            Some((from.to_owned(), to.trim_end_matches('\r').to_owned()))
+        })
+        .filter(|(from, _)| !from.is_empty())
+        .collect();
+    if table.is_empty() {
+        return Ok(None);
+    }
+    let mut terms: Vec<&String> = table.keys().collect();
+    terms.sort_by_key(|t| std::cmp::Reverse(t.chars().count()));
+    let pattern = terms
+        .into_iter()
+        .map(|t| regex::escape(t))
+        .collect::<Vec<_>>()
+        .join("|");
+    let re = Regex::new(&pattern).map_err(|_| "replace table too large")?;
+    Ok(Some((re, table)))
+}
+
+impl Rules {
+    /// Parse "pattern => replacement" lines of `rules`, `$1` etc. in
+    /// replacement for groups of the pattern, and "from<TAB>to" lines of
+    /// `table`, replaced as they are. One space each side of "=>" is
+    /// dropped, so replacements may start or end with others.
+    /// Return `None` if there is no rule.
+    pub fn new(s: &str, table: &str) -> Result<Option<Self>, &'static str> {
+        let mut rules = Vec::new();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let (pattern, replacement) =
+                line.split_once("=>").ok_or("replace rule without \"=>\"")?;
+            let pattern = pattern.strip_suffix(' ').unwrap_or(pattern);
+            let replacement = replacement.strip_prefix(' ').unwrap_or(replacement);
+            let re = Regex::new(pattern).map_err(|_| "invalid replace pattern")?;
+            rules.push((re, replacement.to_owned()));
+        }
+        let table = parse_table(table)?;
+        if rules.is_empty() && table.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Rules { rules, table }))
+    }
+
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for (re, replacement) in self.rules.iter() {
+            if let Cow::Owned(replaced) = re.replace_all(&text, replacement.as_str()) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        if let Some((re, table)) = &self.table {
+            if let Cow::Owned(replaced) = re.replace_all(&text, |c: &Captures| table[&c[0]].clone())
+            {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let rules = Rules::new("(\\w+)-san => $1\ncolour=>color\n\n\\.\\.\\. => … ", "")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            rules.apply("Tanaka-san, the colour...ok"),
+            "Tanaka, the color… ok"
+        );
+        assert!(matches!(rules.apply("nothing"), Cow::Borrowed(_)));
+        assert!(Rules::new("", "").unwrap().is_none());
+        assert!(Rules::new("no arrow", "").is_err());
+        assert!(Rules::new("( => x", "").is_err());
+    }
+
+    #[test]
+    fn test_table() {
+        let table = "New\tNeu\r\nNew York\tNY\n\tignored\nno tab\nA.B\tab\n";
+        let rules = Rules::new("", table).unwrap().unwrap();
+        assert_eq!(
+            rules.apply("New York, New Jersey, A.B, AxB"),
+            "NY, Neu Jersey, ab, AxB"
+        );
+        // rules first, then the table
+        let rules = Rules::new("York => Amsterdam", table).unwrap().unwrap();
+        assert_eq!(rules.apply("New York"), "Neu Amsterdam");
+    }
+}
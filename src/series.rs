@@ -0,0 +1,120 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+lazy_static! {
+    /// S01E02, 1x02
+    static ref RE_SXE: Regex =
+        Regex::new(r"(?i)\bS(\d{1,2})\s*E(\d{1,4})|\b(\d{1,2})x(\d{2,3})\b").unwrap();
+    /// 第3集, 第03话
+    static ref RE_CJK_EP: Regex = Regex::new(r"第\s*(\d{1,4})\s*[集话話回]").unwrap();
+    /// EP02, E02, Episode 2, " - 02 "
+    static ref RE_EP: Regex =
+        Regex::new(r"(?i)\b(?:EP?|Episode)\s*(\d{1,4})\b|\s-\s(\d{1,4})\b").unwrap();
+    /// 第2季, Season 2, S2
+    static ref RE_SEASON: Regex =
+        Regex::new(r"(?i)第\s*(\d{1,2})\s*季|\bSeason\s*(\d{1,2})\b|\bS(\d{1,2})\b").unwrap();
+    /// ".zh.forced.srt", ".part2.srt", ".chapters.txt"
+    static ref RE_SUFFIX: Regex = Regex::new(
+        r"(?:\.(?:[a-z]{2,3}(?:-[A-Za-z]{2,4})?|forced|part\d+|chapters))*\.\w+$"
+    ).unwrap();
+}
+
+fn number(caps: &Captures, groups: &[usize]) -> Option<u32> {
+    groups
+        .iter()
+        .filter_map(|&i| caps.get(i))
+        .find_map(|m| m.as_str().parse().ok())
+}
+
+/// Find (season, episode) in a file name, and where it's mentioned.
+/// Season is 1 if only episode number found.
+fn episode(name: &str) -> Option<(u32, u32, usize, usize)> {
+    if let Some(caps) = RE_SXE.captures(name) {
+        let m = caps.get(0)?;
+        let season = number(&caps, &[1, 3])?;
+        let episode = number(&caps, &[2, 4])?;
+        return Some((season, episode, m.start(), m.end()));
+    }
+    let caps = RE_CJK_EP.captures(name).or_else(|| RE_EP.captures(name))?;
+    let m = caps.get(0)?;
+    let episode = number(&caps, &[1, 2])?;
+    let season = RE_SEASON
+        .captures(name)
+        .and_then(|c| number(&c, &[1, 2, 3]))
+        .unwrap_or(1);
+    Some((season, episode, m.start(), m.end()))
+}
+
+/// "Show.Name.S01E02.zh.srt" -> "Season 01/Show Name S01E02.zh.srt";
+/// names without episode number are kept.
+pub fn organize(name: &str) -> String {
+    let (season, ep, start, end) = match episode(name) {
+        Some(found) => found,
+        None => return name.to_owned(),
+    };
+    // release group tags, e.g. "[Group] "
+    let mut title = &name[..start];
+    while title.trim_start().starts_with('[') {
+        match title.find(']') {
+            Some(i) => title = &title[i + 1..],
+            None => break,
+        }
+    }
+    let title = title
+        .replace(&['.', '_'][..], " ")
+        .trim_matches(|c: char| c.is_whitespace() || "-[(".contains(c))
+        .to_owned();
+    let suffix = RE_SUFFIX.find(&name[end..]).map_or("", |m| m.as_str());
+    let title = if title.is_empty() {
+        String::new()
+    } else {
+        title + " "
+    };
+    format!(
+        "Season {:02}/{}S{:02}E{:02}{}",
+        season, title, season, ep, suffix
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_organize() {
+        assert_eq!(
+            organize("Show.Name.S01E02.zh.srt"),
+            "Season 01/Show Name S01E02.zh.srt"
+        );
+        assert_eq!(
+            organize("show_name s2 e10.forced.srt"),
+            "Season 02/show name S02E10.forced.srt"
+        );
+        assert_eq!(
+            organize("Show Name 1x05.srt"),
+            "Season 01/Show Name S01E05.srt"
+        );
+        assert_eq!(organize("某剧 第3集.srt"), "Season 01/某剧 S01E03.srt");
+        assert_eq!(
+            organize("[字幕组] 某剧 第2季 第03话.chs.srt"),
+            "Season 02/某剧 第2季 S02E03.chs.srt"
+        );
+        assert_eq!(organize("某剧 第12回.srt"), "Season 01/某剧 S01E12.srt");
+        assert_eq!(organize("Show EP07.srt"), "Season 01/Show S01E07.srt");
+        assert_eq!(
+            organize("Show Season 3 Episode 4.en.srt"),
+            "Season 03/Show Season 3 S03E04.en.srt"
+        );
+        assert_eq!(
+            organize("[Group] Show - 05 [1080p].part2.srt"),
+            "Season 01/Show S01E05.part2.srt"
+        );
+    }
+
+    #[test]
+    fn test_organize_no_episode() {
+        assert_eq!(organize("Movie.2019.zh.srt"), "Movie.2019.zh.srt");
+        assert_eq!(organize("某电影.srt"), "某电影.srt");
+        assert_eq!(organize("notes.txt"), "notes.txt");
+    }
+}
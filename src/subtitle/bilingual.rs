@@ -0,0 +1,58 @@
+use super::{
+    dialogues, finish, format::Format, read_script, style, ConvertMeta, Dialogue, Options,
+};
+
+/// Merge two ASS scripts into one bilingual subtitle, lines of `lower`
+/// under the ones of `upper`. A lower dialogue goes with the upper one it
+/// overlaps most if that covers at least half of it, or stays on its own.
+pub fn merge<F>(
+    upper: &str,
+    lower: &str,
+    opts: &Options,
+    mut mapper: Option<F>,
+) -> Result<(String, ConvertMeta), &'static str>
+where
+    F: FnMut(String) -> Option<String>,
+{
+    if opts.format == Format::Ass {
+        return Err("clean ASS output is for single files only");
+    }
+    let opts = &Options {
+        comments: false,
+        chapter_comments: false,
+        ..opts.clone()
+    };
+    let (upper, _) = read_script(upper, opts);
+    let (lower, _) = read_script(lower, opts);
+    let uppers = dialogues(&upper, opts, &mut mapper, &mut 0)?;
+    let lowers = dialogues(&lower, opts, &mut mapper, &mut 0)?;
+    let mut below: Vec<Vec<Dialogue>> = uppers.iter().map(|_| Vec::new()).collect();
+    let mut merged = Vec::new();
+    for d in lowers {
+        let best = uppers
+            .iter()
+            .enumerate()
+            .map(|(i, u)| (i, u.overlap(&d)))
+            .filter(|&(_, o)| o > 0 && o * 2 >= d.end.0 - d.start.0)
+            .max_by_key(|&(_, o)| o);
+        match best {
+            Some((i, _)) => below[i].push(d),
+            None => merged.push(d),
+        }
+    }
+    for (mut d, under) in uppers.into_iter().zip(below) {
+        if !under.is_empty() {
+            let under: Vec<_> = under.iter().map(|l| l.text.as_ref()).collect();
+            d.text = format!("{}\r\n{}", d.text, under.join(" ")).into();
+        }
+        merged.push(d);
+    }
+    merged.sort();
+    let mut styles = style::parse_styles(&upper);
+    for style in style::parse_styles(&lower) {
+        if styles.iter().all(|s| s.name != style.name) {
+            styles.push(style);
+        }
+    }
+    Ok(finish(merged, &styles, &[], opts))
+}
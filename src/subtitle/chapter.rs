@@ -0,0 +1,116 @@
+use super::{Centisec, Dialogue, Options};
+use serde::Deserialize;
+
+/// Layout of the chapters file.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChapterFormat {
+    /// "mm:ss Title" lines for video description
+    #[default]
+    YouTube,
+    /// "CHAPTER01=00:00:00.000" & "CHAPTER01NAME=Title" pairs, for
+    /// mkvmerge and most players
+    Ogm,
+}
+
+impl Centisec {
+    /// "m:ss" or "h:mm:ss" as YouTube shows
+    fn to_chapter_time(self) -> String {
+        let secs = self.0 / 100;
+        let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+        if h > 0 {
+            format!("{}:{:02}:{:02}", h, m, s)
+        } else {
+            format!("{:02}:{:02}", m, s)
+        }
+    }
+
+    /// "hh:mm:ss.mmm" as in OGM chapters
+    fn to_ogm_time(self) -> String {
+        let secs = self.0 / 100;
+        format!(
+            "{:02}:{:02}:{:02}.{:03}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            self.0 % 100 * 10
+        )
+    }
+}
+
+impl<'a> Dialogue<'a> {
+    fn is_chapter(&self, opts: &Options) -> bool {
+        if self.comment {
+            return opts.chapter_comments;
+        }
+        opts.chapter_style.as_ref().is_some_and(|s| s == self.style)
+            || opts
+                .chapter_regex
+                .as_ref()
+                .is_some_and(|re| re.is_match(&self.text))
+    }
+}
+
+/// Start & title of chapters in order.
+fn titles(dialogues: &[Dialogue], opts: &Options) -> Vec<(Centisec, String)> {
+    let mut chapters: Vec<(Centisec, String)> = Vec::new();
+    for d in dialogues.iter().filter(|d| d.is_chapter(opts)) {
+        let title = d.text.replace("\r\n", " ");
+        // same title repeated on consecutive events
+        if chapters.last().is_some_and(|(_, t)| *t == title) {
+            continue;
+        }
+        chapters.push((d.start, title));
+    }
+    chapters
+}
+
+/// Collect chapter titles into a chapters file of `opts.chapter_format`,
+/// return it (if any chapter found) and warnings on its requirements.
+pub(super) fn chapters(dialogues: &[Dialogue], opts: &Options) -> (Option<String>, Vec<String>) {
+    let chapters = titles(dialogues, opts);
+    if chapters.is_empty() {
+        return (None, vec![]);
+    }
+    match opts.chapter_format {
+        ChapterFormat::YouTube => youtube(&chapters),
+        ChapterFormat::Ogm => (Some(ogm(&chapters)), vec![]),
+    }
+}
+
+fn ogm(chapters: &[(Centisec, String)]) -> String {
+    chapters
+        .iter()
+        .enumerate()
+        .map(|(i, (t, title))| {
+            format!(
+                "CHAPTER{0:02}={1}\r\nCHAPTER{0:02}NAME={2}\r\n",
+                i + 1,
+                t.to_ogm_time(),
+                title
+            )
+        })
+        .collect()
+}
+
+/// "mm:ss Title" lines for video description, and warnings on YouTube's
+/// requirements.
+fn youtube(chapters: &[(Centisec, String)]) -> (Option<String>, Vec<String>) {
+    let mut warnings = Vec::new();
+    if chapters[0].0.as_secs() >= 1.0 {
+        warnings.push("chapters: first not at 00:00, YouTube will ignore the list".into());
+    }
+    if chapters.len() < 3 {
+        warnings.push("chapters: YouTube needs at least 3 of them".into());
+    }
+    let short = chapters
+        .windows(2)
+        .filter(|w| (w[1].0).0 - (w[0].0).0 < 1000);
+    for w in short {
+        warnings.push(format!("chapters: \"{}\" shorter than 10 seconds", w[0].1));
+    }
+    let list = chapters
+        .iter()
+        .map(|(t, title)| format!("{} {}\r\n", t.to_chapter_time(), title))
+        .collect();
+    (Some(list), warnings)
+}
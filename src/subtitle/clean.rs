@@ -0,0 +1,99 @@
+use super::DialogueFormat;
+
+/// Sections of embedded binaries, dropped when cleaning.
+const BINARY_SECTIONS: &[&str] = &["[Fonts]", "[Graphics]"];
+
+/// Run `mapper` on text outside of `{...}` override tags.
+fn map_text<F>(text: &str, mapper: &mut F) -> Option<String>
+where
+    F: FnMut(String) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (plain, tag) = match rest.find('{') {
+            Some(i) => {
+                let end = rest[i..].find('}').map_or(rest.len(), |j| i + j + 1);
+                (&rest[..i], &rest[i..end])
+            }
+            None => (rest, ""),
+        };
+        if !plain.is_empty() {
+            out += &mapper(plain.to_owned())?;
+        }
+        out += tag;
+        rest = &rest[plain.len() + tag.len()..];
+    }
+    Some(out)
+}
+
+/// Re-emit ASS script: without embedded fonts & graphics or blank lines,
+/// events sorted by start if `sort`, and their text (not tags) through
+/// `mapper`.
+/// Events the mapper returns `None` on are removed.
+pub(super) fn clean<F>(
+    ass: &str,
+    sort: bool,
+    mapper: &mut Option<F>,
+) -> Result<String, &'static str>
+where
+    F: FnMut(String) -> Option<String>,
+{
+    let mut out = String::with_capacity(ass.len());
+    let mut section = "";
+    // format line, and where in `out` its events go
+    let mut format: Option<(DialogueFormat, usize)> = None;
+    let mut events = Vec::new();
+    for line in ass.lines().map(|l| l.trim_end()) {
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            section = line;
+            if BINARY_SECTIONS.contains(&section) {
+                continue;
+            }
+            if !out.is_empty() {
+                out += "\r\n";
+            }
+        } else if BINARY_SECTIONS.contains(&section) {
+            continue;
+        } else if section == "[Events]" {
+            if line.starts_with("Format:") && format.is_none() {
+                let at = out.len() + line.len() + 2;
+                format = Some((DialogueFormat::new(line)?, at));
+            } else if let Some((format, _)) = &format {
+                if let Ok(d) = format.parse(line) {
+                    events.push((d.start, line));
+                    continue;
+                }
+            }
+        }
+        out += line;
+        out += "\r\n";
+    }
+    let (format, at) = format.ok_or("[Events] or Foramt line not found")?;
+    // Text is the last column, may contain commas
+    let text_at = format.cols.len() - 1;
+    if sort {
+        events.sort_by_key(|(start, _)| *start);
+    }
+    let mut lines = String::new();
+    for (_, line) in events {
+        match mapper {
+            Some(ref mut f) => {
+                let (kind, fields) = line.split_at(line.find(':').unwrap_or(0) + 1);
+                let fields: Vec<_> = fields.splitn(text_at + 1, ',').collect();
+                let text = match map_text(fields[text_at], f) {
+                    Some(text) => text,
+                    None => continue,
+                };
+                lines += &format!("{}{},{}", kind, fields[..text_at].join(","), text);
+            }
+            None => lines += line,
+        }
+        lines += "\r\n";
+    }
+    out.insert_str(at, &lines);
+    Ok(out)
+}
@@ -0,0 +1,82 @@
+use super::Dialogue;
+
+/// (first, last, name, may be missing in fonts of old devices)
+const BLOCKS: &[(u32, u32, &str, bool)] = &[
+    (0x0000, 0x007f, "Basic Latin", false),
+    (0x0080, 0x024f, "Latin Extended", false),
+    (0x0370, 0x03ff, "Greek", false),
+    (0x0400, 0x052f, "Cyrillic", false),
+    (0x0590, 0x05ff, "Hebrew", false),
+    (0x0600, 0x077f, "Arabic", false),
+    (0x0e00, 0x0e7f, "Thai", false),
+    (0x2000, 0x206f, "General Punctuation", false),
+    (0x2070, 0x218f, "Letterlike & Number Forms", false),
+    (0x2190, 0x2bff, "Symbols & Arrows", true),
+    (0x3000, 0x303f, "CJK Punctuation", false),
+    (0x3040, 0x30ff, "Kana", false),
+    (0x3100, 0x318f, "Bopomofo & Hangul Jamo", false),
+    (0x3400, 0x4dbf, "CJK Extension A", true),
+    (0x4e00, 0x9fff, "CJK Unified Ideographs", false),
+    (0xac00, 0xd7af, "Hangul", false),
+    (0xe000, 0xf8ff, "Private Use", true),
+    (0xf900, 0xfaff, "CJK Compatibility", true),
+    (0xfe00, 0xfe0f, "Variation Selectors", true),
+    (0xff00, 0xffef, "Fullwidth Forms", false),
+    (0x1f000, 0x1faff, "Emoji & Pictographs", true),
+    (0x20000, 0x3134f, "CJK Extension B+", true),
+];
+
+/// Block of a char, `None` for those not listed.
+fn block(c: char) -> Option<&'static (u32, u32, &'static str, bool)> {
+    let c = c as u32;
+    BLOCKS
+        .iter()
+        .find(|(first, last, _, _)| (*first..=*last).contains(&c))
+}
+
+/// Unicode blocks used by dialogues, and warnings on characters that may
+/// not render with limited fonts: one per block with a few samples and
+/// where it first appears.
+pub(super) fn coverage(dialogues: &[Dialogue]) -> (Vec<&'static str>, Vec<String>) {
+    let mut used: Vec<&'static str> = Vec::new();
+    // (block, samples, count, first dialogue)
+    let mut rare: Vec<(&'static str, String, usize, usize)> = Vec::new();
+    for (i, d) in dialogues.iter().enumerate().filter(|(_, d)| !d.comment) {
+        for c in d.text.chars().filter(|c| !c.is_control()) {
+            let (name, risky) = match block(c) {
+                Some((_, _, name, risky)) => (*name, *risky),
+                None if (c as u32) > 0xffff => ("Other Supplementary", true),
+                None => ("Other", false),
+            };
+            if !used.contains(&name) {
+                used.push(name);
+            }
+            if !risky {
+                continue;
+            }
+            match rare.iter_mut().find(|(n, _, _, _)| *n == name) {
+                Some((_, samples, count, _)) => {
+                    *count += 1;
+                    if samples.chars().count() < 5 && !samples.contains(c) {
+                        samples.push(c);
+                    }
+                }
+                None => rare.push((name, c.to_string(), 1, i)),
+            }
+        }
+    }
+    let warnings = rare
+        .into_iter()
+        .map(|(name, samples, count, i)| {
+            format!(
+                "may not render: {} {} char(s) like {}, first at #{} {}",
+                count,
+                name,
+                samples,
+                i + 1,
+                dialogues[i].start
+            )
+        })
+        .collect();
+    (used, warnings)
+}
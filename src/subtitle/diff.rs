@@ -0,0 +1,128 @@
+use super::{dialogues, read_script, Dialogue, Options};
+use std::fmt::Write;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+enum Change<'a, 'b> {
+    Added(&'b Dialogue<'a>),
+    Removed(&'b Dialogue<'a>),
+    Changed(&'b Dialogue<'a>, &'b Dialogue<'a>),
+}
+
+impl<'a> Dialogue<'a> {
+    pub(super) fn overlap(&self, other: &Dialogue) -> u32 {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        end.0.saturating_sub(start.0)
+    }
+
+    fn same_as(&self, other: &Dialogue) -> bool {
+        self.start == other.start && self.end == other.end && self.text == other.text
+    }
+
+    fn as_diff_line(&self) -> String {
+        format!(
+            "[{} --> {}] {}",
+            self.start,
+            self.end,
+            self.text.replace("\r\n", " / ")
+        )
+    }
+}
+
+impl<'a, 'b> Change<'a, 'b> {
+    fn start(&self) -> u32 {
+        match self {
+            Change::Added(d) | Change::Removed(d) | Change::Changed(d, _) => d.start.0,
+        }
+    }
+}
+
+/// Align dialogues of two ASS scripts by time and text, return a text
+/// report of added/removed/changed dialogues and its summary.
+pub fn compare<F>(
+    a: &str,
+    b: &str,
+    opts: &Options,
+    mut mapper: Option<F>,
+) -> Result<(String, DiffSummary), &'static str>
+where
+    F: FnMut(String) -> Option<String>,
+{
+    let opts = &Options {
+        comments: false,
+        chapter_comments: false,
+        ..opts.clone()
+    };
+    let (a, _) = read_script(a, opts);
+    let (b, _) = read_script(b, opts);
+    let a = dialogues(&a, opts, &mut mapper, &mut 0)?;
+    let b = dialogues(&b, opts, &mut mapper, &mut 0)?;
+    let mut summary = DiffSummary::default();
+    let mut b_used = vec![false; b.len()];
+    let mut a_left = Vec::new();
+
+    // identical dialogues
+    for d in a.iter() {
+        match (0..b.len()).find(|&i| !b_used[i] && b[i].same_as(d)) {
+            Some(i) => {
+                b_used[i] = true;
+                summary.unchanged += 1;
+            }
+            None => a_left.push(d),
+        }
+    }
+    // changed: pair with the unused one overlaps most, at least half of
+    // the longer dialogue
+    let mut changes = Vec::new();
+    for d in a_left {
+        let best = (0..b.len())
+            .filter(|&i| !b_used[i])
+            .map(|i| (i, d.overlap(&b[i])))
+            .filter(|&(i, o)| {
+                o > 0 && o * 2 >= (d.end.0 - d.start.0).max(b[i].end.0 - b[i].start.0)
+            })
+            .max_by_key(|&(_, o)| o);
+        match best {
+            Some((i, _)) => {
+                b_used[i] = true;
+                summary.changed += 1;
+                changes.push(Change::Changed(d, &b[i]));
+            }
+            None => {
+                summary.removed += 1;
+                changes.push(Change::Removed(d));
+            }
+        }
+    }
+    for (d, _) in b.iter().zip(b_used).filter(|(_, used)| !used) {
+        summary.added += 1;
+        changes.push(Change::Added(d));
+    }
+    changes.sort_by_key(|c| c.start());
+
+    let mut report = format!(
+        "{} added, {} removed, {} changed, {} unchanged\r\n\r\n",
+        summary.added, summary.removed, summary.changed, summary.unchanged
+    );
+    for change in changes {
+        match change {
+            Change::Added(d) => write!(report, "+ {}\r\n", d.as_diff_line()),
+            Change::Removed(d) => write!(report, "- {}\r\n", d.as_diff_line()),
+            Change::Changed(old, new) => write!(
+                report,
+                "~ {}\r\n  {}\r\n",
+                old.as_diff_line(),
+                new.as_diff_line()
+            ),
+        }
+        .unwrap();
+    }
+    Ok((report, summary))
+}
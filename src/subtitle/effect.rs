@@ -0,0 +1,90 @@
+use super::{format::Format, Dialogue, Options};
+use serde::Deserialize;
+
+/// What to do with moving (Banner, Scroll) events.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Effects {
+    #[default]
+    Drop,
+    /// keep as static text, warn about each
+    Warn,
+    /// WebVTT: scroll up in a REGION, banners full width; as `Warn` for SRT
+    Region,
+}
+
+/// Effect field of an event.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(super) enum Effect {
+    /// "Banner;delay[;lefttoright;fadeawaywidth]"
+    Banner,
+    /// "Scroll up;y1;y2;delay[;fadeawayheight]" or "Scroll down;...",
+    /// `top` is the smaller y in script pixels
+    Scroll {
+        top: u32,
+    },
+    /// original line of a karaoke template, usually commented out
+    Karaoke,
+    /// generated by a karaoke template, one for each syllable or more
+    Fx,
+    Other,
+}
+
+impl Effect {
+    pub(super) fn parse(field: &str) -> Option<Self> {
+        let mut params = field.split(';').map(|p| p.trim());
+        let name = params.next()?.to_lowercase();
+        match name.as_str() {
+            "" => None,
+            "banner" => Some(Effect::Banner),
+            "karaoke" => Some(Effect::Karaoke),
+            "fx" => Some(Effect::Fx),
+            "scroll up" | "scroll down" => {
+                let mut y = params.take(2).map(|p| p.parse().unwrap_or(0));
+                let (y1, y2) = (y.next().unwrap_or(0), y.next().unwrap_or(0));
+                Some(Effect::Scroll { top: y1.min(y2) })
+            }
+            _ => Some(Effect::Other),
+        }
+    }
+}
+
+impl<'a> Dialogue<'a> {
+    pub(super) fn is_moving(&self) -> bool {
+        matches!(
+            self.effect,
+            Some(Effect::Banner) | Some(Effect::Scroll { .. })
+        )
+    }
+
+    /// Whether to keep the event according to its effect.
+    pub(super) fn keep_effect(&self, opts: &Options) -> bool {
+        match self.effect {
+            None => true,
+            Some(Effect::Karaoke) if opts.karaoke_lines => true,
+            Some(Effect::Fx) if opts.karaoke_lines => false,
+            Some(Effect::Karaoke) | Some(Effect::Fx) | Some(Effect::Other) => !opts.no_effect,
+            Some(_) => opts.effects != Effects::Drop,
+        }
+    }
+}
+
+/// Warnings on moving events kept as static text.
+pub(super) fn static_warnings(dialogues: &[Dialogue], opts: &Options) -> Vec<String> {
+    let placed = opts.effects == Effects::Region && opts.format == Format::WebVtt;
+    if opts.effects == Effects::Drop || placed {
+        return vec![];
+    }
+    dialogues
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| d.is_moving())
+        .map(|(i, d)| {
+            format!(
+                "banner/scroll effect shown static #{} {}: {}",
+                i + 1,
+                d.start,
+                d.text.replace("\r\n", " / ")
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+use super::{Dialogue, Options};
+
+/// Whether `name` matches `pattern` with `*` & `?` wildcards, ignoring
+/// case.
+fn glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // after the last `*`, and where in name it started to match
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Names or patterns separated by commas or lines.
+pub fn parse_patterns(s: &str) -> Vec<String> {
+    s.split(&[',', '\n'][..])
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_owned())
+        .collect()
+}
+
+/// In `include` if any, and not in `exclude`.
+fn included(name: &str, include: &[String], exclude: &[String]) -> bool {
+    let matches = |patterns: &[String]| patterns.iter().any(|p| glob(p, name));
+    (include.is_empty() || matches(include)) && !matches(exclude)
+}
+
+impl<'a> Dialogue<'a> {
+    /// Whether to keep the event according to its style, actor & layer.
+    pub(super) fn keep_filtered(&self, opts: &Options) -> bool {
+        included(self.style, &opts.include_styles, &opts.exclude_styles)
+            && included(self.actor, &opts.include_actors, &opts.exclude_actors)
+            && opts.min_layer.is_none_or(|l| self.layer >= l)
+            && opts.max_layer.is_none_or(|l| self.layer <= l)
+    }
+}
@@ -0,0 +1,51 @@
+/// Decode ASS-flavored UUencode: each char is 6 bits plus 33, every 4
+/// chars make 3 bytes, a short last group makes fewer bytes.
+fn uudecode(data: &str) -> Vec<u8> {
+    let sixes: Vec<u32> = data
+        .bytes()
+        .filter(|b| (33..=96).contains(b))
+        .map(|b| u32::from(b - 33))
+        .collect();
+    let mut bytes = Vec::with_capacity(sixes.len() * 3 / 4);
+    for group in sixes.chunks(4) {
+        let n = group
+            .iter()
+            .chain([0; 4].iter())
+            .take(4)
+            .fold(0, |n, s| n << 6 | s);
+        let three = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        bytes.extend_from_slice(&three[..group.len() - 1]);
+    }
+    bytes
+}
+
+/// Embedded fonts in [Fonts] section, return (file name, data).
+pub fn extract_fonts(ass: &str) -> Vec<(String, Vec<u8>)> {
+    let (ass, _) = super::line_endings(ass);
+    let mut fonts: Vec<(String, String)> = Vec::new();
+    let lines = ass
+        .lines()
+        .skip_while(|l| !l.starts_with("[Fonts]"))
+        .skip(1)
+        .take_while(|l| !l.starts_with('['))
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty());
+    for line in lines {
+        if let Some(name) = line.strip_prefix("fontname:") {
+            fonts.push((name.trim().to_owned(), String::new()));
+        } else if let Some((_, data)) = fonts.last_mut() {
+            data.push_str(line);
+        }
+    }
+    fonts
+        .into_iter()
+        .filter_map(|(name, data)| {
+            // no directory in name
+            let name = name.rsplit(&['/', '\\'][..]).next()?;
+            if name.is_empty() || name.starts_with('.') {
+                return None;
+            }
+            Some((name.to_owned(), uudecode(&data)))
+        })
+        .collect()
+}
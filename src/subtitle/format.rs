@@ -0,0 +1,425 @@
+use super::{
+    effect::Effects,
+    karaoke,
+    qc::{self, Script},
+    region,
+    style::{class_name, Style},
+    Centisec, Dialogue, Options,
+};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Format {
+    #[default]
+    Srt,
+    WebVtt,
+    /// frame-based "{start}{end}text" .sub
+    MicroDvd,
+    /// "[mm:ss.xx]text" lyrics
+    Lrc,
+    /// TTML in IMSC1 text profile
+    Ttml,
+    /// YouTube .sbv
+    Sbv,
+    /// SAMI .smi for legacy players
+    Sami,
+    /// plain text without timing
+    Transcript,
+    /// `[{start_ms, end_ms, text, style, actor}]`
+    Json,
+    /// events table for spreadsheets
+    Csv,
+    /// normalized ASS, see `clean::clean()`
+    Ass,
+    /// Audacity label track
+    Labels,
+}
+
+impl Format {
+    /// Distinct for each format, so extra formats don't collide in a zip.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Srt => "srt",
+            Format::WebVtt => "vtt",
+            Format::MicroDvd => "sub",
+            Format::Lrc => "lrc",
+            Format::Ttml => "ttml",
+            Format::Sbv => "sbv",
+            Format::Sami => "smi",
+            Format::Transcript => "txt",
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Ass => "ass",
+            Format::Labels => "labels.txt",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Format::Srt => "text/srt",
+            Format::WebVtt => "text/vtt",
+            Format::MicroDvd | Format::Lrc | Format::Sbv | Format::Transcript | Format::Labels => {
+                "text/plain"
+            }
+            Format::Ttml => "application/ttml+xml",
+            Format::Sami => "application/smil",
+            Format::Json => "application/json",
+            Format::Csv => "text/csv",
+            Format::Ass => "text/x-ssa",
+        }
+    }
+}
+
+const TTML_HEAD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:ttp="http://www.w3.org/ns/ttml#parameter" xmlns:tts="http://www.w3.org/ns/ttml#styling" ttp:profile="http://www.w3.org/ns/ttml/profile/imsc1/text" xml:lang="">
+<head>
+<layout>
+<region xml:id="bottom" tts:origin="10% 10%" tts:extent="80% 80%" tts:displayAlign="after" tts:textAlign="center"/>
+<region xml:id="top" tts:origin="10% 10%" tts:extent="80% 80%" tts:displayAlign="before" tts:textAlign="center"/>
+</layout>
+</head>
+<body>
+<div>
+"#;
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// NOTE block; "-->" is not allowed in it.
+fn note(text: &str) -> String {
+    format!("NOTE {}\r\n\r\n", text.replace("-->", "->"))
+}
+
+impl<'a> Dialogue<'a> {
+    /// In `region` if any, else placed by cue settings.
+    fn as_vtt(&self, id: usize, with_class: bool, region: Option<&str>) -> String {
+        if self.comment {
+            return note(&self.text);
+        }
+        let mut text = escape_vtt(&self.text);
+        if with_class && !self.style.is_empty() {
+            text = format!("<c.{}>{}</c>", class_name(self.style), text);
+        }
+        let settings = match (region, self.layout) {
+            (Some(r), _) => format!(" region:{}", r),
+            (None, Some(layout)) => layout.cue_settings(),
+            (None, None) => String::new(),
+        };
+        format!(
+            "{}\r\n{} --> {}{}\r\n{}\r\n\r\n",
+            id,
+            self.start.to_string_with('.'),
+            self.end.to_string_with('.'),
+            settings,
+            text
+        )
+    }
+}
+
+fn to_srt<'a, I>(dialogues: I) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    dialogues
+        .into_iter()
+        .enumerate()
+        .map(|(i, d)| d.as_srt(i + 1))
+        .collect()
+}
+
+fn to_vtt<'a, I>(dialogues: I, styles: &[Style], notes: &[String], opts: &Options) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let dialogues: Vec<_> = dialogues.into_iter().collect();
+    let regions = region::regions(
+        dialogues
+            .iter()
+            .filter(|d| opts.vtt_regions || (opts.effects == Effects::Region && d.is_moving())),
+    );
+    let mut vtt = String::from("WEBVTT\r\n\r\n");
+    if !notes.is_empty() {
+        vtt += &note(&notes.join("\r\n"));
+    }
+    regions.iter().for_each(|r| vtt += &r.as_vtt());
+    if opts.vtt_styles && !styles.is_empty() {
+        vtt += "STYLE\r\n";
+        styles.iter().for_each(|s| vtt += &s.as_css());
+        vtt += "\r\n";
+    }
+    for (i, d) in dialogues.iter().enumerate() {
+        let region = region::find(&regions, d.layout);
+        vtt += &d.as_vtt(i + 1, opts.vtt_styles || opts.vtt_classes, region);
+    }
+    vtt
+}
+
+/// MicroDVD with "{1}{1}fps" header; "|" between lines, italic styles
+/// marked by `{Y:i}`.
+fn to_microdvd<'a, I>(dialogues: I, styles: &[Style], opts: &Options) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let fps = if opts.fps > 0.0 { opts.fps } else { 23.976 };
+    let frame = |t: Centisec| (t.as_secs() * fps).round() as u32;
+    let mut sub = format!("{{1}}{{1}}{}\r\n", fps);
+    for d in dialogues.into_iter().filter(|d| !d.comment) {
+        let italic = styles.iter().any(|s| s.name == d.style && s.italic);
+        sub += &format!(
+            "{{{}}}{{{}}}{}{}\r\n",
+            frame(d.start),
+            frame(d.end),
+            if italic { "{Y:i}" } else { "" },
+            d.text.replace("\r\n", "|")
+        );
+    }
+    sub
+}
+
+/// "mm:ss.xx" of LRC
+fn lrc_time(t: Centisec) -> String {
+    format!("{:02}:{:02}.{:02}", t.0 / 6000, t.0 / 100 % 60, t.0 % 100)
+}
+
+/// LRC lyrics timed by start; lines joined by space, and an empty one
+/// clears the previous when the next starts later than it ends.
+/// Enhanced with `<mm:ss.xx>` before karaoke syllables if `lrc_words`.
+fn to_lrc<'a, I>(dialogues: I, opts: &Options) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let stamp = |t: Centisec| format!("[{}]", lrc_time(t));
+    let mut lrc = String::new();
+    let mut last_end: Option<Centisec> = None;
+    for d in dialogues.into_iter().filter(|d| !d.comment) {
+        if let Some(end) = last_end.filter(|&end| end < d.start) {
+            lrc += &format!("{}\r\n", stamp(end));
+        }
+        let mut text = d.text.replace("\r\n", " ");
+        if opts.lrc_words {
+            text = karaoke::syllables(&text, d.start, |t| format!("<{}>", lrc_time(t)));
+        }
+        lrc += &format!("{}{}\r\n", stamp(d.start), text);
+        last_end = Some(last_end.map_or(d.end, |end| end.max(d.end)));
+    }
+    if let Some(end) = last_end {
+        lrc += &format!("{}\r\n", stamp(end));
+    }
+    lrc
+}
+
+/// "H:MM:SS.mmm,H:MM:SS.mmm" then text, no sequence numbers.
+fn to_sbv<'a, I>(dialogues: I) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let time = |t: Centisec| {
+        format!(
+            "{}:{:02}:{:02}.{:03}",
+            t.0 / 100 / 60 / 60,
+            t.0 / 100 / 60 % 60,
+            t.0 / 100 % 60,
+            t.0 % 100 * 10
+        )
+    };
+    dialogues
+        .into_iter()
+        .filter(|d| !d.comment)
+        .map(|d| format!("{},{}\r\n{}\r\n\r\n", time(d.start), time(d.end), d.text))
+        .collect()
+}
+
+/// SAMI class, name & lang for main script of the dialogues.
+fn sami_lang(script: Option<Script>) -> (&'static str, &'static str, &'static str) {
+    match script {
+        Some(Script::Hangul) => ("KRCC", "Korean", "ko-KR"),
+        Some(Script::Cjk) => ("ZHCC", "Chinese", "zh"),
+        Some(Script::Cyrillic) => ("RUCC", "Russian", "ru-RU"),
+        Some(Script::Greek) => ("ELCC", "Greek", "el-GR"),
+        Some(Script::Arabic) => ("ARCC", "Arabic", "ar"),
+        Some(Script::Hebrew) => ("HECC", "Hebrew", "he-IL"),
+        Some(Script::Thai) => ("THCC", "Thai", "th-TH"),
+        Some(Script::Latin) | None => ("ENCC", "English", "en-US"),
+    }
+}
+
+/// SAMI with one language class guessed from the text; a `&nbsp;` sync
+/// clears the previous line when the next starts later than it ends.
+fn to_sami<'a, I>(dialogues: I) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let dialogues: Vec<_> = dialogues.into_iter().filter(|d| !d.comment).collect();
+    let (class, name, lang) = sami_lang(qc::main_script(&dialogues));
+    let mut sami = format!(
+        "<SAMI>\r\n<HEAD>\r\n<STYLE TYPE=\"text/css\">\r\n<!--\r\n\
+         P {{ margin-left:8pt; margin-right:8pt; text-align:center; \
+         font-family:Arial, sans-serif; color:white; }}\r\n\
+         .{} {{ Name:{}; lang:{}; SAMIType:CC; }}\r\n\
+         -->\r\n</STYLE>\r\n</HEAD>\r\n<BODY>\r\n",
+        class, name, lang
+    );
+    let sync = |t: Centisec, text: &str| {
+        format!("<SYNC Start={}><P Class={}>{}\r\n", t.0 * 10, class, text)
+    };
+    let mut last_end: Option<Centisec> = None;
+    for d in dialogues.iter() {
+        if let Some(end) = last_end.filter(|&end| end < d.start) {
+            sami += &sync(end, "&nbsp;");
+        }
+        sami += &sync(d.start, &escape_vtt(&d.text).replace("\r\n", "<br>"));
+        last_end = Some(last_end.map_or(d.end, |end| end.max(d.end)));
+    }
+    if let Some(end) = last_end {
+        sami += &sync(end, "&nbsp;");
+    }
+    sami + "</BODY>\r\n</SAMI>\r\n"
+}
+
+/// Text of dialogues a line each, "Actor: " before it if asked and known.
+fn to_transcript<'a, I>(dialogues: I, opts: &Options) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    dialogues
+        .into_iter()
+        .filter(|d| !d.comment)
+        .map(|d| {
+            let text = d.text.replace("\r\n", " ");
+            if opts.transcript_actors && !d.actor.is_empty() {
+                format!("{}: {}\r\n", d.actor, text)
+            } else {
+                text + "\r\n"
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct JsonCue<'a> {
+    start_ms: u32,
+    end_ms: u32,
+    /// lines separated by "\n"
+    text: String,
+    style: &'a str,
+    actor: &'a str,
+}
+
+fn to_json<'a, I>(dialogues: I) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let cues: Vec<_> = dialogues
+        .into_iter()
+        .filter(|d| !d.comment)
+        .map(|d| JsonCue {
+            start_ms: d.start.0 * 10,
+            end_ms: d.end.0 * 10,
+            text: d.text.replace("\r\n", "\n"),
+            style: d.style,
+            actor: d.actor,
+        })
+        .collect();
+    serde_json::to_string(&cues).expect("cues are always serializable")
+}
+
+/// "start\tend\ttext" in secs, as Audacity imports & exports labels.
+fn to_labels<'a, I>(dialogues: I) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    // exact, unlike f32 secs
+    let secs = |t: Centisec| format!("{}.{:02}0000", t.0 / 100, t.0 % 100);
+    dialogues
+        .into_iter()
+        .filter(|d| !d.comment)
+        .map(|d| {
+            format!(
+                "{}\t{}\t{}\n",
+                secs(d.start),
+                secs(d.end),
+                d.text.replace("\r\n", " ").replace('\t', " ")
+            )
+        })
+        .collect()
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains(&[',', '"', '\r', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// index, start, end, duration in secs, style, actor & text per row.
+fn to_csv<'a, I>(dialogues: I) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let mut csv = String::from("index,start,end,duration,style,actor,text\r\n");
+    for (i, d) in dialogues.into_iter().filter(|d| !d.comment).enumerate() {
+        csv += &format!(
+            "{},{},{},{:.2},{},{},{}\r\n",
+            i + 1,
+            d.start.to_string_with('.'),
+            d.end.to_string_with('.'),
+            Centisec(d.end.0 - d.start.0).as_secs(),
+            csv_field(d.style),
+            csv_field(d.actor),
+            csv_field(&d.text)
+        );
+    }
+    csv
+}
+
+/// Minimal IMSC1 document, a `<p>` per dialogue in top or bottom region.
+fn to_ttml<'a, I>(dialogues: I) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    let mut ttml = TTML_HEAD.replace('\n', "\r\n");
+    for d in dialogues.into_iter().filter(|d| !d.comment) {
+        let top = matches!(d.layout, Some(l) if l.is_top());
+        ttml += &format!(
+            "<p begin=\"{}\" end=\"{}\" region=\"{}\">{}</p>\r\n",
+            d.start.to_string_with('.'),
+            d.end.to_string_with('.'),
+            if top { "top" } else { "bottom" },
+            escape_vtt(&d.text).replace("\r\n", "<br/>")
+        );
+    }
+    ttml + "</div>\r\n</body>\r\n</tt>\r\n"
+}
+
+/// Serialize dialogues in `opts.format`.
+pub(super) fn write<'a, I>(
+    dialogues: I,
+    styles: &[Style],
+    notes: &[String],
+    opts: &Options,
+) -> String
+where
+    I: IntoIterator<Item = Dialogue<'a>>,
+{
+    match opts.format {
+        Format::Srt => to_srt(dialogues),
+        Format::WebVtt => to_vtt(dialogues, styles, notes, opts),
+        Format::MicroDvd => to_microdvd(dialogues, styles, opts),
+        Format::Lrc => to_lrc(dialogues, opts),
+        Format::Ttml => to_ttml(dialogues),
+        Format::Sbv => to_sbv(dialogues),
+        Format::Sami => to_sami(dialogues),
+        Format::Transcript => to_transcript(dialogues, opts),
+        Format::Json => to_json(dialogues),
+        Format::Csv => to_csv(dialogues),
+        Format::Labels => to_labels(dialogues),
+        // written from the script, not dialogues
+        Format::Ass => unreachable!("ASS passes through clean()"),
+    }
+}
@@ -0,0 +1,158 @@
+use super::{Centisec, Rounding};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Header of the script made from other formats; a single default style
+/// on VSFilter's default resolution.
+const ASS_HEAD: &str = "[Script Info]
+ScriptType: v4.00+
+PlayResX: 384
+PlayResY: 288
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, \
+Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Default,Arial,20,&H00FFFFFF,&H000000FF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,2,2,\
+2,10,10,10,1
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+";
+
+/// Parse "hh:mm:ss,mmm" (or "." before millisecs) to centisec.
+fn parse_time(s: &str, rounding: Rounding) -> Option<Centisec> {
+    let (hms, ms) = s.trim().split_at(s.trim().rfind(&[',', '.'][..])?);
+    let mut secs = 0;
+    for n in hms.split(':') {
+        secs = secs * 60 + n.parse::<u32>().ok()?;
+    }
+    let ms: u64 = ms[1..].parse().ok()?;
+    Some(Centisec(secs * 100 + rounding.div(ms, 10) as u32))
+}
+
+/// "h:mm:ss.cc" as in ASS events.
+fn ass_time(t: Centisec) -> String {
+    let t = t.0;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        t / 100 / 60 / 60,
+        t / 100 / 60 % 60,
+        t / 100 % 60,
+        t % 100
+    )
+}
+
+/// HTML-like tags of SRT & WebVTT into ASS override tags; unknown ones
+/// and WebVTT timestamps removed.
+fn srt_tags(text: &str) -> String {
+    lazy_static! {
+        static ref RE_TAG: Regex = Regex::new(r"</?[A-Za-z][^>]*>|<\d[^>]*>").unwrap();
+    }
+    let mut text = text.to_owned();
+    for tag in &["i", "b", "u", "s"] {
+        for case in &[tag.to_string(), tag.to_uppercase()] {
+            text = text
+                .replace(&format!("<{}>", case), &format!("{{\\{}1}}", tag))
+                .replace(&format!("</{}>", case), &format!("{{\\{}0}}", tag));
+        }
+    }
+    RE_TAG.replace_all(&text, "").into_owned()
+}
+
+fn dialogue(start: Centisec, end: Centisec, text: &str) -> String {
+    format!(
+        "Dialogue: 0,{},{},Default,,0,0,0,,{}\n",
+        ass_time(start),
+        ass_time(end),
+        text
+    )
+}
+
+/// Blocks of non-empty lines.
+fn blocks(text: &str) -> Vec<Vec<&str>> {
+    let mut blocks = vec![];
+    let mut block = vec![];
+    for line in text.lines().map(|l| l.trim_end()) {
+        if line.is_empty() {
+            if !block.is_empty() {
+                blocks.push(block);
+                block = vec![];
+            }
+        } else {
+            block.push(line);
+        }
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// (start, end) of a "start --> end" line, settings after it ignored.
+fn timing(line: &str, rounding: Rounding) -> Option<(Centisec, Centisec)> {
+    let mut times = line.splitn(2, "-->");
+    let start = parse_time(times.next()?, rounding)?;
+    let end = parse_time(times.next()?.split_whitespace().next()?, rounding)?;
+    Some((start, end))
+}
+
+/// SRT as an ASS script with one default style, `None` if not SRT.
+/// With `keep_tags` its HTML-like tags are left as they are, to be
+/// written back to SRT.
+fn srt_to_ass(srt: &str, keep_tags: bool, rounding: Rounding) -> Option<String> {
+    let mut ass = String::from(ASS_HEAD);
+    let mut found = false;
+    for block in blocks(srt) {
+        let at = block.iter().position(|l| l.contains("-->"))?;
+        if at > 1 {
+            return None;
+        }
+        let (start, end) = timing(block[at], rounding)?;
+        let text = block[at + 1..].join("\\N");
+        let text = if keep_tags { text } else { srt_tags(&text) };
+        ass += &dialogue(start, end, &text);
+        found = true;
+    }
+    if found {
+        Some(ass)
+    } else {
+        None
+    }
+}
+
+/// WebVTT as an ASS script with one default style, cue settings and
+/// NOTE, STYLE & REGION blocks dropped.
+fn vtt_to_ass(vtt: &str, rounding: Rounding) -> String {
+    let mut ass = String::from(ASS_HEAD);
+    // first one is the WEBVTT header
+    for block in blocks(vtt).iter().skip(1) {
+        let at = match block.iter().position(|l| l.contains("-->")) {
+            Some(at) if at <= 1 => at,
+            _ => continue,
+        };
+        if let Some((start, end)) = timing(block[at], rounding) {
+            let text = srt_tags(&block[at + 1..].join("\\N"))
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&nbsp;", "\u{a0}")
+                .replace("&amp;", "&");
+            ass += &dialogue(start, end, &text);
+        }
+    }
+    ass
+}
+
+/// Input of other subtitle formats as an ASS script; `None` for ASS or
+/// unknown ones, which are left to the ASS parser. See `srt_to_ass()` for
+/// `keep_tags`; millisecs are rounded by `rounding`.
+pub(super) fn to_ass(text: &str, keep_tags: bool, rounding: Rounding) -> Option<String> {
+    let text = text.trim_start_matches('\u{feff}');
+    if text.starts_with("WEBVTT") {
+        return Some(vtt_to_ass(text, rounding));
+    }
+    if text.contains("[Events]") {
+        return None;
+    }
+    srt_to_ass(text, keep_tags, rounding)
+}
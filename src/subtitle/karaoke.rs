@@ -0,0 +1,66 @@
+use super::{Centisec, Dialogue};
+
+/// Around centisecs from the start of dialogue to a syllable, put into
+/// text as it survives `cleanse_text()` and text mappers.
+const MARK_START: char = '\u{e000}';
+const MARK_END: char = '\u{e001}';
+
+/// Durations in centisecs of `\k`, `\K`, `\kf` & `\ko` tags in a
+/// `{...}` block.
+fn karaoke_tags(block: &str) -> Vec<u32> {
+    block
+        .split('\\')
+        .filter_map(|tag| {
+            let tag = tag.trim_end_matches('}');
+            ["kf", "ko", "k", "K"]
+                .iter()
+                .filter(|k| tag.starts_with(*k))
+                .find_map(|k| tag[k.len()..].parse().ok())
+        })
+        .collect()
+}
+
+impl<'a> Dialogue<'a> {
+    /// Mark where each karaoke syllable starts. Must be called before
+    /// `cleanse_text()`; see `syllables()`.
+    pub(super) fn mark_syllables(&mut self) {
+        if !self.text.contains("\\k") && !self.text.contains("\\K") {
+            return;
+        }
+        let mut text = String::with_capacity(self.text.len());
+        let mut offset = 0;
+        let mut rest: &str = &self.text;
+        while let Some(open) = rest.find('{') {
+            let close = rest[open..].find('}').map_or(rest.len(), |i| open + i + 1);
+            let durations = karaoke_tags(&rest[open..close]);
+            text += &rest[..close];
+            if !durations.is_empty() {
+                text += &format!("{}{}{}", MARK_START, offset, MARK_END);
+                offset += durations.iter().sum::<u32>();
+            }
+            rest = &rest[close..];
+        }
+        text += rest;
+        self.text = text.into();
+    }
+}
+
+/// Replace syllable marks of a dialogue starting at `start` with
+/// `stamp` of their time.
+pub(super) fn syllables<F>(text: &str, start: Centisec, stamp: F) -> String
+where
+    F: Fn(Centisec) -> String,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find(MARK_START) {
+        out += &rest[..open];
+        rest = &rest[open + MARK_START.len_utf8()..];
+        let close = rest.find(MARK_END).unwrap_or(rest.len());
+        if let Ok(offset) = rest[..close].parse::<u32>() {
+            out += &stamp(Centisec(start.0 + offset));
+        }
+        rest = rest[close..].trim_start_matches(MARK_END);
+    }
+    out + rest
+}
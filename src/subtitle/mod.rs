@@ -1,23 +1,240 @@
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::Deserialize;
 use std::{borrow::Cow, cmp::Ordering, collections::HashMap, fmt, str::FromStr};
 
+use chapter::ChapterFormat;
+use effect::{Effect, Effects};
+use format::Format;
+use region::Layout;
+use rtl::Bidi;
+use style::Style;
+use wrap::LineBreak;
+
+pub mod bilingual;
+pub mod chapter;
+mod clean;
+mod coverage;
+pub mod diff;
+pub mod effect;
+pub mod filter;
+pub mod fonts;
+pub mod format;
+mod input;
+mod karaoke;
+mod qc;
+mod region;
+pub mod rtl;
+mod sdh;
+mod sign;
+pub mod style;
+mod tags;
 #[cfg(test)]
 mod tests;
+pub mod timeline;
+pub mod wrap;
 
 struct DialogueFormat {
     cols: HashMap<String, usize>,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 struct Centisec(u32);
 
-#[derive(PartialEq, Eq)]
+/// How times between centisecs are rounded: millisecs of SRT & WebVTT
+/// input, and shifted, retimed or rescaled times. Start and end are
+/// rounded by the same rule, so a cue never ends before it starts.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rounding {
+    #[default]
+    Nearest,
+    Floor,
+    Ceil,
+}
+
+impl Rounding {
+    /// `num / den` rounded.
+    fn div(self, num: u64, den: u64) -> u64 {
+        match self {
+            Rounding::Nearest => (num + den / 2) / den,
+            Rounding::Floor => num / den,
+            Rounding::Ceil => num.div_ceil(den),
+        }
+    }
+
+    /// Centisecs rounded, ignoring the noise of f32 secs.
+    fn round(self, t: f64) -> f64 {
+        match self {
+            Rounding::Nearest => t.round(),
+            Rounding::Floor => (t + 1e-3).floor(),
+            Rounding::Ceil => (t - 1e-3).ceil(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// skip dialogues with effect field other than Banner and Scroll
+    pub no_effect: bool,
+    pub effects: Effects,
+    /// keep only dialogues of these styles if any, and skip the ones of
+    /// `exclude_styles`; `*` & `?` wildcards allowed
+    pub include_styles: Vec<String>,
+    pub exclude_styles: Vec<String>,
+    /// the same by actor (Name field), for scripts marking signs or notes
+    /// there
+    pub include_actors: Vec<String>,
+    pub exclude_actors: Vec<String>,
+    /// keep only dialogues within the Layer range, typesetting is often
+    /// on higher ones
+    pub min_layer: Option<i32>,
+    pub max_layer: Option<i32>,
+    /// (source, corrected) secs sorted by source, interpolated linearly
+    /// in between; applied before offset
+    pub anchors: Vec<(f32, f32)>,
+    /// frame rate of the script & of the video it goes with, timings
+    /// rescaled if both set; applied before anchors
+    pub fps_from: f32,
+    pub fps_to: f32,
+    /// snap times to frames of this rate if set, with a frame between
+    /// cues that would touch; applied last
+    pub snap_fps: f32,
+    pub offset_secs: f32,
+    pub rounding: Rounding,
+    /// (start, end) secs removed from video, sorted & non-overlapping
+    pub cuts: Vec<(f32, f32)>,
+    /// keep only dialogues within the time range (after offset applied)
+    pub trim_start_secs: Option<f32>,
+    pub trim_end_secs: Option<f32>,
+    /// shift trimmed dialogues so that trim start becomes zero
+    pub trim_rebase: bool,
+    /// keep events in script order rather than sorted by start time,
+    /// which some players require
+    pub keep_order: bool,
+    /// extend shorter dialogues up to this, but not past the next one
+    pub min_duration_secs: f32,
+    /// dialogues of the same text overlapping or touching each other,
+    /// e.g. copies on layers for border effects, become one
+    pub merge_duplicates: bool,
+    /// re-wrap longer lines at spaces or between CJK characters; zero
+    /// for no limit
+    pub max_chars_per_line: usize,
+    /// split dialogues of more lines into consecutive ones, time divided
+    /// by length; zero for no limit
+    pub max_lines: usize,
+    /// what `\N` & `\n` become, new line by default
+    pub hard_breaks: LineBreak,
+    pub soft_breaks: LineBreak,
+    /// skip typesetting events guessed by their tags, style and timing
+    pub drop_signs: bool,
+    /// keep only the typesetting events `drop_signs` would skip
+    pub only_signs: bool,
+    /// remove SDH sound descriptions like `[door slams]`, and dialogues
+    /// left empty
+    pub strip_sdh: bool,
+    /// mark output as forced subtitle regardless of its content
+    pub forced: bool,
+    /// warn on dialogues not in the main language of the file
+    pub check_untranslated: bool,
+    /// list Unicode blocks used, warn on characters old fonts may lack
+    pub check_coverage: bool,
+    /// reading speed limit in characters per second, 20 if zero
+    pub max_cps: f32,
+    /// style name -> `<font color>`, `None` for no color; SRT only
+    pub style_colors: HashMap<String, Option<String>>,
+    pub format: Format,
+    /// frames per second for MicroDVD, 23.976 if zero
+    pub fps: f32,
+    /// WebVTT: add STYLE block from ASS styles and tag cues with them
+    pub vtt_styles: bool,
+    /// WebVTT: tag cues with `<c.style>` only, for CSS of the player page
+    pub vtt_classes: bool,
+    /// WebVTT: place cues into REGIONs from ASS alignment and margins
+    pub vtt_regions: bool,
+    /// SRT: `\i1`, `\b1` & `\u1` overrides, and the same turned on by the
+    /// style, as `<i>`, `<b>` & `<u>`
+    pub keep_basic_tags: bool,
+    /// SRT: `\c` color overrides as `<font color>`
+    pub keep_colors: bool,
+    /// prefix dialogues with actor (Name field) and this separator, for
+    /// formats without an actor column
+    pub speaker_sep: Option<String>,
+    /// speaker name in upper case, as in SDH
+    pub speaker_upper: bool,
+    /// LRC: enhanced `<mm:ss.xx>` syllable times from karaoke tags
+    pub lrc_words: bool,
+    /// one clean line per karaoke event: the `karaoke` comments a template
+    /// was applied to instead of the `fx` lines generated from them
+    pub karaoke_lines: bool,
+    /// transcript: prefix lines with the actor (Name field)
+    pub transcript_actors: bool,
+    /// keep `Comment:` events and `;` script comments, as NOTE blocks in
+    /// WebVTT or hidden `{# ...}` cues in SRT
+    pub comments: bool,
+    /// `Comment:` events as ordinary cues in brackets, to review
+    /// translator notes
+    pub comment_cues: bool,
+    /// direction marks or display order for Arabic & Hebrew lines
+    pub bidi: Bidi,
+    /// dialogues of this style, or matching this regex, are chapter titles
+    pub chapter_style: Option<String>,
+    pub chapter_regex: Option<Regex>,
+    /// `Comment:` events are chapter titles, left out of output unless
+    /// `comments`
+    pub chapter_comments: bool,
+    pub chapter_format: ChapterFormat,
+}
+
+/// Information collected during conversion.
+#[derive(Debug, Default)]
+pub struct ConvertMeta {
+    /// number of dialogues in output
+    pub dialogues: usize,
+    pub warnings: Vec<String>,
+    /// "mm:ss Title" lines for YouTube, if any chapter found
+    pub chapters: Option<String>,
+    /// time covered by dialogues, overlaps counted once
+    pub duration_secs: f32,
+    /// main writing system, e.g. "Latin", "CJK"
+    pub script: Option<&'static str>,
+    /// forced subtitle by `Options::forced` or its content
+    pub forced: bool,
+    /// Unicode blocks used, if `Options::check_coverage`
+    pub blocks: Vec<&'static str>,
+    /// vector drawing events skipped
+    pub drawings: usize,
+    /// dialogues faster than `Options::max_cps`, and the fastest speed
+    pub fast_cues: usize,
+    pub max_cps: f32,
+    /// chars the output charset can't encode, replaced if
+    /// ignoring encoding errors
+    pub lossy_chars: usize,
+    /// input charset, detected or chosen; filled by the caller
+    pub charset: Option<&'static str>,
+    /// input chars replaced by U+FFFD if ignoring encoding errors
+    pub decode_errors: usize,
+}
+
+#[derive(PartialEq, Eq, Clone)]
 struct Dialogue<'a> {
     start: Centisec,
     end: Centisec,
     text: Cow<'a, str>,
-    effect: bool,
+    style: &'a str,
+    /// Name field
+    actor: &'a str,
+    /// MarginL, MarginR, MarginV; zero for the style's
+    margins: [u32; 3],
+    layout: Option<Layout>,
+    /// Layer field, lower ones first among simultaneous events
+    layer: i32,
+    /// percent from bottom of the screen, see `Layout::height()`
+    height: u32,
+    /// guessed typesetting, see `is_sign()`
+    sign: bool,
+    effect: Option<Effect>,
+    /// from `Comment:` instead of `Dialogue:` line
+    comment: bool,
 }
 
 impl DialogueFormat {
@@ -35,7 +252,8 @@ impl DialogueFormat {
     }
 
     fn parse<'a>(&self, line: &'a str) -> Result<Dialogue<'a>, &'static str> {
-        let cols: Vec<_> = line[9..]
+        let (kind, line) = line.split_at(line.find(':').ok_or("not an event line")?);
+        let cols: Vec<_> = line[1..]
             .splitn(self.cols.len(), ',')
             .map(|c| c.trim())
             .collect();
@@ -43,18 +261,29 @@ impl DialogueFormat {
         let start = get("start").ok_or("'Start' not found")?.parse()?;
         let end = get("end").ok_or("'End' not found")?.parse()?;
         let text = get("text").ok_or("'Text' not found")?;
-        let effect = get("effect").map(|t| !t.trim().is_empty()).unwrap_or(false);
+        let style = get("style").cloned().unwrap_or_default();
+        let actor = get("name").cloned().unwrap_or_default();
+        let effect = get("effect").and_then(|t| Effect::parse(t));
+        let margin = |col| get(col).and_then(|m| m.parse().ok()).unwrap_or(0);
         Ok(Dialogue {
             start,
             end,
             effect,
+            style,
+            actor,
+            margins: [margin("marginl"), margin("marginr"), margin("marginv")],
+            layout: None,
+            layer: get("layer").and_then(|l| l.parse().ok()).unwrap_or(0),
+            height: 0,
+            sign: false,
+            comment: kind == "Comment",
             text: Cow::from(*text),
         })
     }
 }
 
 impl<'a> Dialogue<'a> {
-    fn cleanse_text(&mut self) {
+    fn cleanse_text(&mut self, hard: LineBreak, soft: LineBreak) {
         lazy_static! {
             static ref RE_CMD: Regex = Regex::new(
                 // remove:
@@ -62,35 +291,62 @@ impl<'a> Dialogue<'a> {
                 // {...} (other cmds}
                 r"\{[^\}]*\\p[1-9][^\}]*\}.*?(\{[^\}]*\\p0[^\}]*\}|$)|\{.*?\}"
             ).unwrap();
-            static ref RE_LINE: Regex = Regex::new(
-                r"\\[Nn]"
-            ).unwrap();
         }
         self.text = {
             let text = RE_CMD.replace_all(&self.text, "");
-            let text = RE_LINE.replace_all(&text, "\r\n");
-            text.into_owned()
+            let text = hard.replace(&text, "\\N");
+            soft.replace(&text, "\\n")
         }
         .into();
     }
 
     fn as_srt(&self, id: usize) -> String {
+        if self.comment {
+            return format!(
+                "{}\r\n{} --> {}\r\n{{# {}}}\r\n\r\n",
+                id,
+                self.start,
+                self.end,
+                self.text.replace("\r\n", " ").replace('}', ")")
+            );
+        }
         format!(
             "{}\r\n{} --> {}\r\n{}\r\n\r\n",
             id, self.start, self.end, self.text
         )
     }
+
+    /// Clip dialogue into [start, end), return false if nothing left;
+    /// zero length comments (chapter marks) are kept within the range.
+    fn trim(&mut self, start: Option<Centisec>, end: Option<Centisec>) -> bool {
+        if let Some(start) = start {
+            self.start = self.start.max(start);
+        }
+        if let Some(end) = end {
+            self.end = self.end.min(end);
+        }
+        self.end > self.start || self.comment && self.end == self.start
+    }
 }
 
+/// By start time; simultaneous events by layer, then from bottom of the
+/// screen to top (dialogue before signs), with comments ahead of the line
+/// they annotate, so output doesn't depend on their order in the script.
 impl<'a> Ord for Dialogue<'a> {
     fn cmp(&self, other: &Dialogue) -> Ordering {
-        self.start.cmp(&other.start)
+        self.start
+            .cmp(&other.start)
+            .then(self.layer.cmp(&other.layer))
+            .then(self.height.cmp(&other.height))
+            .then(self.end.cmp(&other.end))
+            .then(other.comment.cmp(&self.comment))
+            .then_with(|| self.text.cmp(&other.text))
     }
 }
 
 impl<'a> PartialOrd for Dialogue<'a> {
     fn partial_cmp(&self, other: &Dialogue) -> Option<Ordering> {
-        Some(self.start.cmp(&other.start))
+        Some(self.cmp(other))
     }
 }
 
@@ -111,31 +367,101 @@ impl FromStr for Centisec {
     }
 }
 
-/// convert centisecs to "hh:mm:ss.mmm"
+/// convert centisecs to "hh:mm:ss,mmm"
 impl fmt::Display for Centisec {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.to_string_with(','))
+    }
+}
+
+impl Centisec {
+    /// "hh:mm:ss{sep}mmm"
+    fn to_string_with(self, sep: char) -> String {
         let t = self.0;
         let h = t / 100 / 60 / 60;
         let m = t / 100 / 60 % 60;
         let s = t / 100 % 60;
         let ms = t % 100 * 10;
-        write!(f, "{:02}:{:02}:{:02},{:03}", h, m, s, ms)
+        format!("{:02}:{:02}:{:02}{}{:03}", h, m, s, sep, ms)
+    }
+
+    fn as_secs(&self) -> f32 {
+        self.0 as f32 / 100.0
+    }
+
+    fn from_secs(secs: f32) -> Self {
+        let mut t = Centisec(0);
+        t.add_secs(secs, Rounding::Nearest);
+        t
+    }
+
+    fn add_secs(&mut self, secs: f32, rounding: Rounding) {
+        let secs = self.0 as f64 + secs as f64 * 100.0;
+        self.0 = if secs <= 0.0 {
+            0
+        } else {
+            rounding.round(secs) as u32
+        }
+    }
+
+    /// Scale time by `from / to`, both (numerator, denominator).
+    fn rescale(&mut self, from: (u64, u64), to: (u64, u64), rounding: Rounding) {
+        let num = self.0 as u64 * from.0 * to.1;
+        let den = from.1 * to.0;
+        self.0 = rounding.div(num, den) as u32;
+    }
+
+    /// Map time through (source, corrected) anchors. Between two anchors
+    /// interpolate, outside them extend the nearest segment; a single
+    /// anchor is a plain shift.
+    fn retime(&mut self, anchors: &[(f32, f32)], rounding: Rounding) {
+        let t = self.as_secs();
+        let (s0, c0, slope) = match anchors {
+            [] => return,
+            [(s, c)] => (*s, *c, 1.0),
+            _ => {
+                let i = anchors.iter().position(|(s, _)| *s > t);
+                let i = i.unwrap_or(anchors.len()).max(1).min(anchors.len() - 1);
+                let ((s0, c0), (s1, c1)) = (anchors[i - 1], anchors[i]);
+                (s0, c0, (c1 - c0) / (s1 - s0))
+            }
+        };
+        *self = Centisec(0);
+        self.add_secs(c0 + (t - s0) * slope, rounding);
+    }
+
+    /// Move time backward by the length of cuts before it.
+    /// Time inside a cut goes to the start of the cut.
+    fn remap(&mut self, cuts: &[(Centisec, Centisec)]) {
+        let t = self.0;
+        let removed: u32 = cuts
+            .iter()
+            .take_while(|(start, _)| start.0 < t)
+            .map(|(start, end)| t.min(end.0) - start.0)
+            .sum();
+        self.0 -= removed;
     }
 }
 
-impl Centisec {
-    fn add_secs(&mut self, secs: f32) {
-        let secs = self.0 as f32 + (secs * 100.0);
-        self.0 = if secs <= 0.0 { 0 } else { secs.round() as u32 }
+/// Frame rate as an exact fraction; NTSC rates like 23.976 are
+/// 24000/1001, others taken to 1/1000 fps.
+fn fps_fraction(fps: f32) -> (u64, u64) {
+    let ntsc = (fps * 1.001).round();
+    if (ntsc / 1.001 - fps).abs() < 0.001 && (ntsc - fps).abs() > 0.01 {
+        (ntsc as u64 * 1000, 1001)
+    } else {
+        ((fps * 1000.0).round() as u64, 1000)
     }
 }
 
-pub fn ass_to_srt<F>(
-    ass: &str,
-    no_effect: bool,
-    mut mapper: Option<F>,
-    offset_secs: f32,
-) -> Result<String, &'static str>
+/// Parse, filter and retime dialogues of an ASS script; events left
+/// empty by removing vector drawings are counted in `drawings`.
+fn dialogues<'a, F>(
+    ass: &'a str,
+    opts: &Options,
+    mapper: &mut Option<F>,
+    drawings: &mut usize,
+) -> Result<Vec<Dialogue<'a>>, &'static str>
 where
     F: FnMut(String) -> Option<String>,
 {
@@ -152,33 +478,413 @@ where
         .ok_or("[Events] or Foramt line not found")?;
     let format = DialogueFormat::new(format)?;
     // parse dialogues
-    let mut dialogues = events
-        .filter(|l| l.starts_with("Dialogue:"))
+    let dialogues = events
+        .filter(|l| l.starts_with("Dialogue:") || l.starts_with("Comment:"))
         .map(|l| format.parse(l))
         .filter_map(|d| d.ok())
-        .filter(|d| !no_effect || !d.effect)
+        .map(|mut d| {
+            // the original lines karaoke templates were applied to
+            if opts.karaoke_lines && d.effect == Some(Effect::Karaoke) {
+                d.comment = false;
+            } else if opts.comment_cues && d.comment {
+                d.comment = false;
+                d.text = format!("[{}]", d.text).into();
+            }
+            d
+        })
+        .filter(|d| !d.comment || opts.comments || opts.chapter_comments)
+        .filter(|d| d.keep_effect(opts) && d.keep_filtered(opts))
         .collect::<Vec<_>>();
-    // to srt
-    dialogues.sort();
-    let mut id = 0;
-    Ok(dialogues
+    let cuts: Vec<_> = opts
+        .cuts
+        .iter()
+        .map(|(s, e)| (Centisec::from_secs(*s), Centisec::from_secs(*e)))
+        .collect();
+    let fps_ratio = Some((fps_fraction(opts.fps_from), fps_fraction(opts.fps_to)))
+        .filter(|_| opts.fps_from > 0.0 && opts.fps_to > 0.0 && opts.fps_from != opts.fps_to);
+    let trim_start = opts.trim_start_secs.map(Centisec::from_secs);
+    let trim_end = opts.trim_end_secs.map(Centisec::from_secs);
+    let rebase = trim_start
+        .filter(|_| opts.trim_rebase)
+        .unwrap_or(Centisec(0));
+    // styles & resolution for layout of simultaneous events and regions
+    let styles = style::parse_styles(ass);
+    let play_res = region::play_res(ass);
+    let effect_regions = opts.effects == Effects::Region && opts.format == Format::WebVtt;
+    let speakers = !matches!(
+        opts.format,
+        Format::Transcript | Format::Json | Format::Csv | Format::Labels
+    );
+    let mut dialogues: Vec<_> = dialogues
         .into_iter()
         .filter_map(|mut d| {
-            d.cleanse_text();
-            if d.text.is_empty() {
+            // decided before cleansing drops the \p tags
+            let drawing = !d.comment && d.is_drawing();
+            if !d.comment {
+                let layout = d.resolve_layout(&styles, play_res);
+                d.height = layout.height();
+                d.layout = Some(layout);
+                if effect_regions && d.is_moving() {
+                    d.layout = d.effect.map(|e| layout.with_effect(e, play_res));
+                }
+                d.sign = d.is_sign();
+                if opts.drop_signs && d.sign || opts.only_signs && !d.sign {
+                    return None;
+                }
+            }
+            if opts.lrc_words && opts.format == Format::Lrc && !d.comment {
+                d.mark_syllables();
+            }
+            let html = opts.keep_basic_tags || opts.keep_colors;
+            if html && opts.format == Format::Srt && !d.comment {
+                d.html_tags(opts.keep_basic_tags, opts.keep_colors, &styles);
+            }
+            d.cleanse_text(opts.hard_breaks, opts.soft_breaks);
+            if opts.strip_sdh && !d.comment {
+                d.strip_sdh();
+            }
+            if d.text.is_empty() && !drawing {
+                return None;
+            }
+            if let Some((from, to)) = fps_ratio {
+                d.start.rescale(from, to, opts.rounding);
+                d.end.rescale(from, to, opts.rounding);
+            }
+            d.start.retime(&opts.anchors, opts.rounding);
+            d.end.retime(&opts.anchors, opts.rounding);
+            d.start.add_secs(opts.offset_secs, opts.rounding);
+            d.end.add_secs(opts.offset_secs, opts.rounding);
+            d.start.remap(&cuts);
+            d.end.remap(&cuts);
+            if !d.trim(trim_start, trim_end) {
                 return None;
             }
-            d.start.add_secs(offset_secs);
-            d.end.add_secs(offset_secs);
+            if drawing && d.text.is_empty() {
+                *drawings += 1;
+                return None;
+            }
+            d.start.0 -= rebase.0;
+            d.end.0 -= rebase.0;
+            if let Some(sep) = opts.speaker_sep.as_ref().filter(|_| speakers) {
+                if !d.comment && !d.actor.is_empty() {
+                    let name = if opts.speaker_upper {
+                        d.actor.to_uppercase()
+                    } else {
+                        d.actor.to_owned()
+                    };
+                    d.text = format!("{}{}{}", name, sep, d.text).into();
+                }
+            }
             if let Some(ref mut f) = mapper {
                 d.text = f(d.text.into())?.into();
             }
+            if opts.max_chars_per_line > 0 && !d.comment {
+                d.text = wrap::wrap(&d.text, opts.max_chars_per_line).into();
+            }
+            if opts.bidi != Bidi::Keep {
+                d.text = rtl::fix_rtl(&d.text, opts.bidi).into();
+            }
+            let color = opts
+                .style_colors
+                .get(d.style)
+                .filter(|_| opts.format == Format::Srt && !d.comment);
+            if let Some(Some(color)) = color {
+                d.text = format!("<font color=\"{}\">{}</font>", color, d.text).into();
+            }
             Some(d)
         })
-        .filter(|d| d.end.0 > d.start.0)
-        .map(|d| {
-            id += 1;
-            d.as_srt(id)
-        })
-        .collect())
+        // chapter marks are often zero length
+        .filter(|d| d.end.0 > d.start.0 || d.comment && opts.chapter_comments)
+        .collect();
+    if !opts.keep_order {
+        dialogues.sort();
+    }
+    if opts.merge_duplicates {
+        dialogues = merge_duplicates(dialogues);
+    }
+    if opts.max_lines > 0 {
+        dialogues = wrap::split_lines(dialogues, opts.max_lines);
+    }
+    if !opts.keep_order && (opts.merge_duplicates || opts.max_lines > 0) {
+        dialogues.sort();
+    }
+    if opts.min_duration_secs > 0.0 {
+        extend_short(&mut dialogues, Centisec::from_secs(opts.min_duration_secs));
+    }
+    if opts.snap_fps > 0.0 {
+        snap_to_frames(&mut dialogues, fps_fraction(opts.snap_fps));
+    }
+    Ok(dialogues)
+}
+
+/// Merge dialogues into the previous one of the same text if it overlaps
+/// or touches them, extending its time.
+fn merge_duplicates(dialogues: Vec<Dialogue>) -> Vec<Dialogue> {
+    let mut merged: Vec<Dialogue> = Vec::with_capacity(dialogues.len());
+    // text -> index in `merged` of its last dialogue
+    let mut last: HashMap<String, usize> = HashMap::new();
+    for d in dialogues {
+        if !d.comment {
+            if let Some(&i) = last.get(d.text.as_ref()) {
+                let m = &mut merged[i];
+                if m.end >= d.start && d.end >= m.start {
+                    m.start = m.start.min(d.start);
+                    m.end = m.end.max(d.end);
+                    continue;
+                }
+            }
+            last.insert(d.text.to_string(), merged.len());
+        }
+        merged.push(d);
+    }
+    merged
+}
+
+/// Extend dialogues shorter than `min`, up to the start of the next one
+/// at most; never shorten them.
+fn extend_short(dialogues: &mut [Dialogue], min: Centisec) {
+    for i in 0..dialogues.len() {
+        let d = &dialogues[i];
+        if d.comment || d.end.0 - d.start.0 >= min.0 {
+            continue;
+        }
+        let next = dialogues[i + 1..]
+            .iter()
+            .find(|n| !n.comment && n.start > d.start)
+            .map(|n| n.start);
+        let end = Centisec(d.start.0 + min.0);
+        let end = next.map_or(end, |next| end.min(next));
+        dialogues[i].end = d.end.max(end);
+    }
+}
+
+/// Snap times to the nearest frame of `fps`, as (numerator, denominator).
+/// Dialogues last a frame at least, and end a frame before the next one
+/// if they would end on or after its first frame, unless that leaves no
+/// frame for them.
+fn snap_to_frames(dialogues: &mut [Dialogue], fps: (u64, u64)) {
+    let frame = |t: Centisec| Rounding::Nearest.div(t.0 as u64 * fps.0, 100 * fps.1);
+    let time = |f: u64| Centisec(Rounding::Nearest.div(f * 100 * fps.1, fps.0) as u32);
+    let frames: Vec<_> = dialogues
+        .iter()
+        .map(|d| (frame(d.start), frame(d.end), d.comment))
+        .collect();
+    for (i, d) in dialogues.iter_mut().enumerate() {
+        let (start, mut end, comment) = frames[i];
+        if !comment {
+            end = end.max(start + 1);
+            let next = frames[i + 1..].iter().find(|n| !n.2 && n.0 > start);
+            if let Some(&(next, _, _)) = next {
+                if end >= next && next > start + 1 {
+                    end = next - 1;
+                }
+            }
+        }
+        d.start = time(start);
+        d.end = time(end);
+    }
+}
+
+/// Total time with any dialogue on screen.
+fn covered(dialogues: &[Dialogue]) -> Centisec {
+    let mut spans: Vec<_> = dialogues
+        .iter()
+        .filter(|d| !d.comment)
+        .map(|d| (d.start, d.end))
+        .collect();
+    spans.sort();
+    let mut total = 0;
+    let mut covered_to = Centisec(0);
+    for (start, end) in spans {
+        if end > covered_to {
+            total += end.0 - start.max(covered_to).0;
+            covered_to = end;
+        }
+    }
+    Centisec(total)
+}
+
+/// Turn bare CR (old Mac) line endings into LF, warn on them and on
+/// mixed CRLF & LF.
+fn line_endings(ass: &str) -> (Cow<'_, str>, Option<String>) {
+    let crlf = ass.matches("\r\n").count();
+    let cr = ass.matches('\r').count() - crlf;
+    let lf = ass.matches('\n').count() - crlf;
+    match (cr, crlf, lf) {
+        (0, 0, _) | (0, _, 0) => (Cow::Borrowed(ass), None),
+        (0, _, _) => (
+            Cow::Borrowed(ass),
+            Some("mixed CRLF & LF line endings".into()),
+        ),
+        (_, 0, 0) => (
+            Cow::Owned(ass.replace('\r', "\n")),
+            Some("old Mac (CR) line endings converted".into()),
+        ),
+        _ => (
+            Cow::Owned(ass.replace("\r\n", "\n").replace('\r', "\n")),
+            Some("mixed line endings normalized".into()),
+        ),
+    }
+}
+
+/// Input text as an ASS script, SRT & WebVTT converted; with the warning of
+/// `line_endings()`. Formatting of SRT input is kept for SRT output.
+fn read_script<'a>(text: &'a str, opts: &Options) -> (Cow<'a, str>, Option<String>) {
+    let (text, warning) = line_endings(text);
+    match input::to_ass(&text, opts.format == Format::Srt, opts.rounding) {
+        Some(ass) => (Cow::Owned(ass), warning),
+        None => (text, warning),
+    }
+}
+
+/// `;` comment lines of the script if `opts.comments`.
+fn script_comments(ass: &str, opts: &Options) -> Vec<String> {
+    if !opts.comments {
+        return vec![];
+    }
+    ass.lines()
+        .filter(|l| l.starts_with(';'))
+        .map(|l| l[1..].trim().to_owned())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Run checks on final dialogues then output them.
+fn finish(
+    mut dialogues: Vec<Dialogue>,
+    styles: &[Style],
+    notes: &[String],
+    opts: &Options,
+) -> (String, ConvertMeta) {
+    let mut meta = ConvertMeta {
+        dialogues: dialogues.iter().filter(|d| !d.comment).count(),
+        duration_secs: covered(&dialogues).as_secs(),
+        script: qc::main_script(&dialogues).map(|s| s.name()),
+        forced: opts.forced || sign::is_forced(&dialogues),
+        ..Default::default()
+    };
+    meta.warnings
+        .extend(effect::static_warnings(&dialogues, opts));
+    let max_cps = if opts.max_cps > 0.0 {
+        opts.max_cps
+    } else {
+        20.0
+    };
+    let (fast_cues, fastest) = qc::reading_speed(&dialogues, max_cps);
+    meta.fast_cues = fast_cues;
+    meta.max_cps = fastest;
+    if fast_cues > 0 {
+        meta.warnings.push(format!(
+            "reading speed: {} cues over {} characters/s, up to {:.1}",
+            fast_cues, max_cps, fastest
+        ));
+    }
+    if opts.check_untranslated {
+        meta.warnings.extend(qc::untranslated(&dialogues));
+    }
+    if opts.check_coverage {
+        let (blocks, warnings) = coverage::coverage(&dialogues);
+        meta.blocks = blocks;
+        meta.warnings.extend(warnings);
+    }
+    if opts.chapter_style.is_some() || opts.chapter_regex.is_some() || opts.chapter_comments {
+        let (chapters, warnings) = chapter::chapters(&dialogues, opts);
+        meta.chapters = chapters;
+        meta.warnings.extend(warnings);
+    }
+    dialogues.retain(|d| !d.comment || opts.comments && d.end.0 > d.start.0);
+    (format::write(dialogues, styles, notes, opts), meta)
+}
+
+pub fn convert<F>(
+    ass: &str,
+    opts: &Options,
+    mut mapper: Option<F>,
+) -> Result<(String, ConvertMeta), &'static str>
+where
+    F: FnMut(String) -> Option<String>,
+{
+    let (ass, warning) = read_script(ass, opts);
+    if opts.format == Format::Ass {
+        let out = clean::clean(&ass, !opts.keep_order, &mut mapper)?;
+        let meta = ConvertMeta {
+            warnings: warning.into_iter().collect(),
+            ..Default::default()
+        };
+        return Ok((out, meta));
+    }
+    let styles = style::parse_styles(&ass);
+    let notes = script_comments(&ass, opts);
+    let mut drawings = 0;
+    let dialogues = dialogues(&ass, opts, &mut mapper, &mut drawings)?;
+    let (out, mut meta) = finish(dialogues, &styles, &notes, opts);
+    meta.warnings.splice(0..0, warning);
+    meta.drawings = drawings;
+    Ok((out, meta))
+}
+
+pub fn ass_to_srt<F>(ass: &str, opts: &Options, mapper: Option<F>) -> Result<String, &'static str>
+where
+    F: FnMut(String) -> Option<String>,
+{
+    convert(ass, opts, mapper).map(|(srt, _)| srt)
+}
+
+/// Join ASS scripts end to end into one subtitle.
+/// `offsets[i]` is the start secs of i-th script; scripts without one
+/// (or with `None`) start right after the last dialogue of previous script.
+pub fn concat_to_srt<F>(
+    scripts: &[&str],
+    offsets: &[Option<f32>],
+    opts: &Options,
+    mut mapper: Option<F>,
+) -> Result<(String, ConvertMeta), &'static str>
+where
+    F: FnMut(String) -> Option<String>,
+{
+    if opts.format == Format::Ass {
+        return Err("clean ASS output is for single files only");
+    }
+    let (scripts, warnings): (Vec<_>, Vec<_>) =
+        scripts.iter().map(|s| read_script(s, opts)).unzip();
+    let mut joined = Vec::new();
+    let mut styles: Vec<Style> = Vec::new();
+    let mut notes: Vec<String> = Vec::new();
+    let mut cursor = 0.0;
+    let mut drawings = 0;
+    for (i, ass) in scripts.iter().enumerate() {
+        let offset = offsets.get(i).cloned().flatten().unwrap_or(cursor);
+        let part_opts = Options {
+            offset_secs: opts.offset_secs + offset,
+            ..opts.clone()
+        };
+        let part = dialogues(ass, &part_opts, &mut mapper, &mut drawings)?;
+        cursor = part
+            .iter()
+            .map(|d| d.end.as_secs() - opts.offset_secs)
+            .fold(offset, f32::max);
+        joined.extend(part);
+        for style in style::parse_styles(ass) {
+            if styles.iter().all(|s| s.name != style.name) {
+                styles.push(style);
+            }
+        }
+        for note in script_comments(ass, opts) {
+            if !notes.contains(&note) {
+                notes.push(note);
+            }
+        }
+    }
+    // parts may overlap with given offsets
+    if !opts.keep_order {
+        joined.sort();
+    }
+    let (out, mut meta) = finish(joined, &styles, &notes, opts);
+    let warnings = warnings
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, w)| Some(format!("part {}: {}", i + 1, w?)));
+    meta.warnings.splice(0..0, warnings);
+    meta.drawings = drawings;
+    Ok((out, meta))
 }
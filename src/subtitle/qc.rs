@@ -0,0 +1,137 @@
+use super::{wrap::width, Centisec, Dialogue};
+use std::collections::HashMap;
+
+/// ignore short ones like "OK", names, etc.
+const MIN_LETTERS: usize = 4;
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub(super) enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Thai,
+    /// Han & Kana
+    Cjk,
+    Hangul,
+}
+
+impl Script {
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Script::Latin => "Latin",
+            Script::Cyrillic => "Cyrillic",
+            Script::Greek => "Greek",
+            Script::Arabic => "Arabic",
+            Script::Hebrew => "Hebrew",
+            Script::Thai => "Thai",
+            Script::Cjk => "CJK",
+            Script::Hangul => "Hangul",
+        }
+    }
+}
+
+/// Script of a letter, `None` for others.
+pub(super) fn script(c: char) -> Option<Script> {
+    let script = match c as u32 {
+        0x0370..=0x03ff => Script::Greek,
+        0x0400..=0x052f => Script::Cyrillic,
+        0x0590..=0x05ff => Script::Hebrew,
+        0x0600..=0x06ff | 0x0750..=0x077f => Script::Arabic,
+        0x0e00..=0x0e7f => Script::Thai,
+        0x1100..=0x11ff | 0x3130..=0x318f | 0xac00..=0xd7af => Script::Hangul,
+        0x3040..=0x30ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xf900..=0xfaff => Script::Cjk,
+        0x20000..=0x2fa1f => Script::Cjk,
+        _ if c.is_alphabetic() => Script::Latin,
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// Letters of each script in text. A CJK character counts as two letters
+/// since it carries about as much as a short word.
+fn letters(text: &str) -> HashMap<Script, usize> {
+    let mut count = HashMap::new();
+    for s in text.chars().filter_map(script) {
+        *count.entry(s).or_insert(0) += if s == Script::Cjk { 2 } else { 1 };
+    }
+    count
+}
+
+fn letter_counts(dialogues: &[Dialogue]) -> Vec<HashMap<Script, usize>> {
+    dialogues
+        .iter()
+        .map(|d| {
+            if d.comment {
+                HashMap::new()
+            } else {
+                letters(&d.text)
+            }
+        })
+        .collect()
+}
+
+/// The script most dialogues mainly written in.
+fn vote(counts: &[HashMap<Script, usize>]) -> Option<Script> {
+    let mut votes = HashMap::new();
+    for count in counts.iter() {
+        if count.values().sum::<usize>() < MIN_LETTERS {
+            continue;
+        }
+        if let Some((s, _)) = count.iter().max_by_key(|(_, n)| **n) {
+            *votes.entry(*s).or_insert(0) += 1;
+        }
+    }
+    votes.into_iter().max_by_key(|(_, n)| *n).map(|(s, _)| s)
+}
+
+/// Main script of the whole file.
+pub(super) fn main_script(dialogues: &[Dialogue]) -> Option<Script> {
+    vote(&letter_counts(dialogues))
+}
+
+/// Find dialogues predominantly written in other script than the main
+/// one of the whole file, e.g. English lines left in a Chinese subtitle.
+pub(super) fn untranslated(dialogues: &[Dialogue]) -> Vec<String> {
+    let counts = letter_counts(dialogues);
+    let main = match vote(&counts) {
+        Some(s) => s,
+        None => return vec![],
+    };
+    dialogues
+        .iter()
+        .zip(counts)
+        .enumerate()
+        .filter(|(_, (_, count))| {
+            let all: usize = count.values().sum();
+            let main = count.get(&main).cloned().unwrap_or(0);
+            all >= MIN_LETTERS && main * 5 < all
+        })
+        .map(|(i, (d, _))| {
+            format!(
+                "untranslated? #{} {}: {}",
+                i + 1,
+                d.start,
+                d.text.replace("\r\n", " / ")
+            )
+        })
+        .collect()
+}
+
+/// Cues read faster than `max_cps` characters per second, line breaks
+/// not counted: (how many, the fastest speed).
+pub(super) fn reading_speed(dialogues: &[Dialogue], max_cps: f32) -> (usize, f32) {
+    let mut fast = 0;
+    let mut fastest = 0f32;
+    for d in dialogues.iter().filter(|d| !d.comment) {
+        let chars: usize = d.text.split("\r\n").map(width).sum();
+        let secs = Centisec(d.end.0 - d.start.0).as_secs();
+        let cps = chars as f32 / secs.max(0.01);
+        if cps > max_cps {
+            fast += 1;
+        }
+        fastest = fastest.max(cps);
+    }
+    (fast, fastest)
+}
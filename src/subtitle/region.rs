@@ -0,0 +1,235 @@
+use super::{effect::Effect, style::Style, Dialogue};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(super) enum VAlign {
+    Bottom,
+    Middle,
+    Top,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(super) enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Where a dialogue goes on screen, margins in percent of the video.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub(super) struct Layout {
+    valign: VAlign,
+    halign: HAlign,
+    left: u32,
+    right: u32,
+    vertical: u32,
+    /// WebVTT `scroll:up`
+    scroll: bool,
+}
+
+pub(super) struct Region {
+    id: String,
+    layout: Layout,
+    /// max lines of its dialogues
+    lines: usize,
+}
+
+/// PlayResX & PlayResY in [Script Info], default to 384x288 as VSFilter.
+pub(super) fn play_res(ass: &str) -> (u32, u32) {
+    let get = |key: &str| {
+        ass.lines()
+            .skip_while(|l| !l.starts_with("[Script Info]"))
+            .skip(1)
+            .take_while(|l| !l.starts_with('['))
+            .filter(|l| l.starts_with(key))
+            .filter_map(|l| l[key.len()..].trim_start_matches(':').trim().parse().ok())
+            .find(|n| *n > 0)
+    };
+    match (get("PlayResX"), get("PlayResY")) {
+        (Some(x), Some(y)) => (x, y),
+        (Some(x), None) => (x, x * 3 / 4),
+        (None, Some(y)) => (y * 4 / 3, y),
+        (None, None) => (384, 288),
+    }
+}
+
+/// Alignment override ({\anN} or legacy {\aN}) in the raw text.
+fn override_alignment(text: &str) -> Option<u8> {
+    lazy_static! {
+        static ref RE_ALIGN: Regex = Regex::new(r"\{[^}]*?\\(an|a)(\d+)").unwrap();
+    }
+    let caps = RE_ALIGN.captures(text)?;
+    let n = caps[2].parse().ok()?;
+    Some(if &caps[1] == "a" {
+        super::style::legacy_alignment(n)
+    } else {
+        n
+    })
+}
+
+impl<'a> Dialogue<'a> {
+    /// Resolve layout from its overrides, margins and style.
+    /// Must be called before `cleanse_text()`.
+    pub(super) fn resolve_layout(&self, styles: &[Style], (res_x, res_y): (u32, u32)) -> Layout {
+        let style = styles.iter().find(|s| s.name == self.style);
+        let align = override_alignment(&self.text)
+            .or_else(|| style.map(|s| s.alignment))
+            .unwrap_or(2);
+        // zero margin in event means the one of style
+        let margin = |i: usize| match self.margins[i] {
+            0 => style.map_or(0, |s| s.margins[i]),
+            m => m,
+        };
+        let left = (margin(0) * 100 / res_x).min(45);
+        let right = (margin(1) * 100 / res_x).min(45);
+        let vertical = (margin(2) * 100 / res_y).min(50);
+        let valign = match align {
+            7..=9 => VAlign::Top,
+            4..=6 => VAlign::Middle,
+            _ => VAlign::Bottom,
+        };
+        let halign = match align % 3 {
+            1 => HAlign::Left,
+            0 => HAlign::Right,
+            _ => HAlign::Center,
+        };
+        Layout {
+            valign,
+            halign,
+            left,
+            right,
+            vertical: if valign == VAlign::Middle {
+                0
+            } else {
+                vertical
+            },
+            scroll: false,
+        }
+    }
+}
+
+/// One region per distinct layout, in order of first appearance.
+pub(super) fn regions<'a, 'b: 'a, I>(dialogues: I) -> Vec<Region>
+where
+    I: IntoIterator<Item = &'a Dialogue<'b>>,
+{
+    let mut regions: Vec<Region> = Vec::new();
+    for d in dialogues {
+        let layout = match d.layout {
+            Some(layout) => layout,
+            None => continue,
+        };
+        let lines = d.text.lines().count();
+        if let Some(r) = regions.iter_mut().find(|r| r.layout == layout) {
+            r.lines = r.lines.max(lines);
+            continue;
+        }
+        let name = match layout.valign {
+            VAlign::Bottom => "bottom",
+            VAlign::Middle => "middle",
+            VAlign::Top => "top",
+        };
+        let n = regions
+            .iter()
+            .filter(|r| r.layout.valign == layout.valign)
+            .count();
+        let id = match n {
+            0 => name.to_owned(),
+            n => format!("{}{}", name, n + 1),
+        };
+        regions.push(Region { id, layout, lines });
+    }
+    regions
+}
+
+/// Id of the region for given layout.
+pub(super) fn find(regions: &[Region], layout: Option<Layout>) -> Option<&str> {
+    let layout = layout?;
+    regions
+        .iter()
+        .find(|r| r.layout == layout)
+        .map(|r| r.id.as_str())
+}
+
+impl Layout {
+    /// Approximate a moving effect: banners go full width, scrolling
+    /// text scrolls up from the top of its area.
+    pub(super) fn with_effect(self, effect: Effect, (_, res_y): (u32, u32)) -> Layout {
+        match effect {
+            Effect::Banner => Layout {
+                left: 0,
+                right: 0,
+                ..self
+            },
+            Effect::Scroll { top } => Layout {
+                valign: VAlign::Top,
+                left: 0,
+                right: 0,
+                vertical: (top * 100 / res_y).min(100),
+                scroll: true,
+                ..self
+            },
+            Effect::Karaoke | Effect::Fx | Effect::Other => self,
+        }
+    }
+
+    /// Percent from bottom of the screen to its edge nearest to the text.
+    pub(super) fn height(&self) -> u32 {
+        match self.valign {
+            VAlign::Bottom => self.vertical,
+            VAlign::Middle => 50,
+            VAlign::Top => 100 - self.vertical,
+        }
+    }
+
+    pub(super) fn is_top(&self) -> bool {
+        self.valign == VAlign::Top
+    }
+
+    /// WebVTT cue settings for cues not in a region, with leading space;
+    /// empty for the default bottom center.
+    pub(super) fn cue_settings(&self) -> String {
+        let line = match self.valign {
+            VAlign::Top => format!(" line:{}%", self.vertical),
+            VAlign::Middle => " line:50%,center".to_owned(),
+            VAlign::Bottom => String::new(),
+        };
+        let align = match self.halign {
+            HAlign::Left => " align:left",
+            HAlign::Center => "",
+            HAlign::Right => " align:right",
+        };
+        line + align
+    }
+}
+
+impl Region {
+    /// WebVTT REGION block.
+    pub(super) fn as_vtt(&self) -> String {
+        let Layout {
+            valign,
+            left,
+            right,
+            vertical,
+            scroll,
+            ..
+        } = self.layout;
+        let (anchor_y, viewport_y) = match valign {
+            VAlign::Bottom => (100, 100 - vertical),
+            VAlign::Middle => (50, 50),
+            VAlign::Top => (0, vertical),
+        };
+        format!(
+            "REGION\r\nid:{}\r\nwidth:{}%\r\nlines:{}\r\n\
+             regionanchor:0%,{}%\r\nviewportanchor:{}%,{}%\r\n{}\r\n",
+            self.id,
+            100 - left - right,
+            self.lines.max(1),
+            anchor_y,
+            left,
+            viewport_y,
+            if scroll { "scroll:up\r\n" } else { "" }
+        )
+    }
+}
@@ -0,0 +1,110 @@
+use serde::Deserialize;
+
+/// How to write lines in right-to-left scripts (Arabic, Hebrew).
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Bidi {
+    #[default]
+    Keep,
+    /// logical order, embedded as RTL with marks around Latin & numbers
+    Logical,
+    /// reversed into display order for players without bidi support
+    Visual,
+}
+
+const RLM: char = '\u{200f}';
+const RLE: char = '\u{202b}';
+const PDF: char = '\u{202c}';
+
+fn is_rtl(c: char) -> bool {
+    matches!(c as u32, 0x0590..=0x08ff | 0xfb1d..=0xfdff | 0xfe70..=0xfeff)
+}
+
+fn is_ltr(c: char) -> bool {
+    c.is_alphanumeric() && !is_rtl(c)
+}
+
+/// Chars kept inside a Latin run if between letters: "3.14", "don't".
+fn is_joiner(c: char) -> bool {
+    " .,:'/-".contains(c)
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        c => c,
+    }
+}
+
+/// Embed line as RTL, and put RLM between Latin or number and punctuation
+/// after it, so the punctuation stays at the end after tags stripped.
+fn logical(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len() + 8);
+    out.push(RLE);
+    for (i, c) in chars.iter().enumerate() {
+        out.push(*c);
+        let next = chars.get(i + 1).cloned().unwrap_or(' ');
+        // not inside "3.14"
+        let after = chars.get(i + 2).cloned().unwrap_or(' ');
+        if is_ltr(*c) && ".,!?:;)]".contains(next) && !is_ltr(after) {
+            out.push(RLM);
+        }
+    }
+    out.push(PDF);
+    out
+}
+
+/// Reverse line into display order, Latin & number runs kept as is.
+fn visual(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut runs: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !is_ltr(chars[i]) {
+            runs.push(mirror(chars[i]).to_string());
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        loop {
+            while end < chars.len() && is_ltr(chars[end]) {
+                end += 1;
+            }
+            let mut next = end;
+            while next < chars.len() && is_joiner(chars[next]) {
+                next += 1;
+            }
+            if next > end && next < chars.len() && is_ltr(chars[next]) {
+                end = next;
+            } else {
+                break;
+            }
+        }
+        runs.push(chars[i..end].iter().collect());
+        i = end;
+    }
+    runs.reverse();
+    runs.concat()
+}
+
+/// Rewrite lines that contain RTL letters, others left as is.
+pub(super) fn fix_rtl(text: &str, bidi: Bidi) -> String {
+    text.split("\r\n")
+        .map(|line| match bidi {
+            _ if !line.chars().any(is_rtl) => line.to_owned(),
+            Bidi::Keep => line.to_owned(),
+            Bidi::Logical => logical(line),
+            Bidi::Visual => visual(line),
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
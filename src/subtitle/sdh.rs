@@ -0,0 +1,31 @@
+use super::Dialogue;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+impl<'a> Dialogue<'a> {
+    /// Remove sound descriptions of SDH: `[door slams]`, `(music)` and
+    /// `♪ lyrics ♪`, then lines left with nothing but tags or a dash.
+    /// Text ends up empty if there is nothing else.
+    /// Must be called after `cleanse_text()`.
+    pub(super) fn strip_sdh(&mut self) {
+        lazy_static! {
+            static ref RE_SDH: Regex = Regex::new(r"\[[^\]]*\]|\([^)]*\)|[♪♫][^♪♫]*[♪♫]").unwrap();
+            static ref RE_HTML: Regex = Regex::new(r"<[^>]*>").unwrap();
+        }
+        if !RE_SDH.is_match(&self.text) {
+            return;
+        }
+        let text = RE_SDH.replace_all(&self.text, "");
+        let lines: Vec<String> = text
+            .split("\r\n")
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|l| {
+                let visible = RE_HTML.replace_all(l, "");
+                !visible
+                    .trim_matches(|c: char| c == '-' || c.is_whitespace())
+                    .is_empty()
+            })
+            .collect();
+        self.text = lines.join("\r\n").into();
+    }
+}
@@ -0,0 +1,58 @@
+use super::{Centisec, Dialogue};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+impl<'a> Dialogue<'a> {
+    /// Guess if it's a typesetting (sign) event rather than dialogue.
+    /// Positioning, clipping, drawing or rotation tags are enough; else
+    /// it needs two of: sign-like style name, not at the bottom, very
+    /// short text, shorter than half a second.
+    /// Must be called before `cleanse_text()` and after layout resolved.
+    pub(super) fn is_sign(&self) -> bool {
+        lazy_static! {
+            static ref RE_TYPESET: Regex =
+                Regex::new(r"\{[^}]*\\(pos|move|i?clip|org|p[1-9]|fr[xyz]?-?[1-9])").unwrap();
+            static ref RE_SIGN_STYLE: Regex =
+                Regex::new(r"(?i)sign|^ts|typeset|title|screen|note").unwrap();
+            static ref RE_TAG: Regex = Regex::new(r"\{[^}]*\}|\\[Nnh]").unwrap();
+        }
+        if RE_TYPESET.is_match(&self.text) {
+            return true;
+        }
+        let letters = RE_TAG
+            .replace_all(&self.text, "")
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .count();
+        let hints = [
+            RE_SIGN_STYLE.is_match(self.style),
+            self.height >= 50,
+            letters <= 2,
+            self.end.0 < self.start.0 + 50,
+        ];
+        hints.iter().filter(|h| **h).count() >= 2
+    }
+}
+
+lazy_static! {
+    static ref RE_DRAWING: Regex = Regex::new(r"\{[^}]*\\p[1-9]").unwrap();
+}
+
+impl<'a> Dialogue<'a> {
+    /// Has vector drawing (`\p1` or more), maybe along with text.
+    pub(super) fn is_drawing(&self) -> bool {
+        RE_DRAWING.is_match(&self.text)
+    }
+}
+
+/// Guess if it's a forced track, only for signs and foreign dialogue:
+/// all events are signs, or fewer than 2 per minute over 10+ minutes.
+pub(super) fn is_forced(dialogues: &[Dialogue]) -> bool {
+    let events: Vec<_> = dialogues.iter().filter(|d| !d.comment).collect();
+    let (first, last) = match (events.first(), events.iter().map(|d| d.end).max()) {
+        (Some(first), Some(last)) => (first.start, last),
+        _ => return false,
+    };
+    let minutes = Centisec(last.0 - first.0).as_secs() / 60.0;
+    events.iter().all(|d| d.sign) || (minutes >= 10.0 && (events.len() as f32) < minutes * 2.0)
+}
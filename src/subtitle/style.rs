@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+/// Parse "StyleName=#rrggbb" lines into style -> font color.
+/// "StyleName=none" (or empty color) keeps the style uncolored.
+pub fn parse_style_colors(s: &str) -> HashMap<String, Option<String>> {
+    s.lines()
+        .filter_map(|line| {
+            let mut kv = line.splitn(2, '=');
+            let style = kv.next()?.trim();
+            let color = kv.next()?.trim();
+            if style.is_empty() {
+                return None;
+            }
+            let color = match color.to_lowercase().as_str() {
+                "" | "none" => None,
+                _ => Some(color.to_owned()),
+            };
+            Some((style.to_owned(), color))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub name: String,
+    /// primary color as "#rrggbb"
+    pub color: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikeout: bool,
+    /// numpad alignment as in ASS
+    pub alignment: u8,
+    /// MarginL, MarginR, MarginV in script pixels
+    pub margins: [u32; 3],
+}
+
+/// Parse "&HAABBGGRR" (ASS) or decimal BGR (SSA) color to "#rrggbb".
+fn parse_color(s: &str) -> Option<String> {
+    let s = s.trim().trim_end_matches('&');
+    let bgr = if s.starts_with("&H") || s.starts_with("&h") {
+        u32::from_str_radix(&s[2..], 16).ok()?
+    } else {
+        s.parse::<i64>().ok()? as u32
+    };
+    let (r, g, b) = (bgr & 0xff, (bgr >> 8) & 0xff, (bgr >> 16) & 0xff);
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+/// SSA alignment (1-3 sub, 5-7 top, 9-11 mid) to numpad one.
+pub(super) fn legacy_alignment(n: u8) -> u8 {
+    match n {
+        5..=7 => n + 2,
+        9..=11 => n - 5,
+        _ => n,
+    }
+}
+
+/// Parse styles in [V4+ Styles] (ASS) or [V4 Styles] (SSA) section.
+pub fn parse_styles(ass: &str) -> Vec<Style> {
+    let mut lines = ass
+        .lines()
+        .skip_while(|l| !l.starts_with("[V4"))
+        .map(|l| l.trim());
+    let ssa = lines.next().is_some_and(|l| l.starts_with("[V4 "));
+    let mut lines = lines.take_while(|l| !l.starts_with('['));
+    let cols: HashMap<String, usize> = match lines.find(|l| l.starts_with("Format:")) {
+        Some(format) => format[7..]
+            .split(',')
+            .map(|c| c.trim().to_lowercase())
+            .enumerate()
+            .map(|(i, n)| (n, i))
+            .collect(),
+        None => return vec![],
+    };
+    lines
+        .filter(|l| l.starts_with("Style:"))
+        .filter_map(|line| {
+            let fields: Vec<_> = line[6..].split(',').map(|f| f.trim()).collect();
+            let get = |col| cols.get(col).and_then(|i| fields.get(*i)).cloned();
+            // -1 for true in ASS, 1 in some scripts
+            let flag = |col| get(col).is_some_and(|v| v != "0");
+            let num = |col| get(col).and_then(|v| v.parse().ok()).unwrap_or(0);
+            let alignment = get("alignment").and_then(|v| v.parse().ok()).unwrap_or(2);
+            Some(Style {
+                name: get("name")?.to_owned(),
+                color: get("primarycolour").and_then(parse_color),
+                bold: flag("bold"),
+                italic: flag("italic"),
+                underline: flag("underline"),
+                strikeout: flag("strikeout"),
+                alignment: if ssa {
+                    legacy_alignment(alignment)
+                } else {
+                    alignment
+                },
+                margins: [num("marginl"), num("marginr"), num("marginv")],
+            })
+        })
+        .collect()
+}
+
+/// Style name to CSS class name.
+pub fn class_name(style: &str) -> String {
+    let name: String = style
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        format!("s{}", name)
+    } else {
+        name
+    }
+}
+
+impl Style {
+    /// WebVTT `::cue()` rule for this style.
+    pub fn as_css(&self) -> String {
+        let mut css = format!("::cue(.{}) {{\r\n", class_name(&self.name));
+        if let Some(ref color) = self.color {
+            css += &format!("  color: {};\r\n", color);
+        }
+        if self.bold {
+            css += "  font-weight: bold;\r\n";
+        }
+        if self.italic {
+            css += "  font-style: italic;\r\n";
+        }
+        match (self.underline, self.strikeout) {
+            (true, true) => css += "  text-decoration: underline line-through;\r\n",
+            (true, false) => css += "  text-decoration: underline;\r\n",
+            (false, true) => css += "  text-decoration: line-through;\r\n",
+            (false, false) => (),
+        }
+        css += "}\r\n";
+        css
+    }
+}
@@ -0,0 +1,130 @@
+use super::{style::Style, Dialogue};
+
+/// (ASS tag, HTML tag) of basic formatting kept in SRT.
+const BASIC: &[(&str, &str)] = &[("i", "i"), ("b", "b"), ("u", "u")];
+
+/// On/off of a basic tag in an override, e.g. "i1", "b700", "u0";
+/// `None` if it's not that tag.
+fn switch(tag: &str, name: &str) -> Option<bool> {
+    if !tag.starts_with(name) {
+        return None;
+    }
+    match tag[name.len()..].parse::<u32>() {
+        Ok(n) => Some(n != 0),
+        Err(_) => None,
+    }
+}
+
+/// "#rrggbb" of a primary color override, e.g. "c&H0000FF&", "1c&HFF&";
+/// `Some(None)` for a bare `\c`, back to the style's color.
+fn color(tag: &str) -> Option<Option<String>> {
+    let value = match tag.strip_prefix("1c") {
+        Some(value) => value,
+        None if !tag.starts_with("clip") => tag.strip_prefix('c')?,
+        None => return None,
+    };
+    let hex = value
+        .trim_start_matches('&')
+        .trim_start_matches(&['H', 'h'][..]);
+    let hex = hex.trim_end_matches('&');
+    if hex.is_empty() {
+        return Some(None);
+    }
+    let bgr = u32::from_str_radix(hex, 16).ok()?;
+    Some(Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        bgr & 0xff,
+        bgr >> 8 & 0xff,
+        bgr >> 16 & 0xff
+    )))
+}
+
+/// HTML tags of basic formatting a style turns on, e.g. by `Italic=1`.
+fn style_tags(style: Option<&Style>) -> Vec<&'static str> {
+    style.map_or_else(Vec::new, |s| {
+        [(s.italic, "i"), (s.bold, "b"), (s.underline, "u")]
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, html)| *html)
+            .collect()
+    })
+}
+
+impl<'a> Dialogue<'a> {
+    /// Put `<i>`, `<b>` & `<u>` where `\i1`, `\b1`, `\u1` overrides (and
+    /// their `0` or `\r`) are, and around the text if its style in `styles`
+    /// has them on, if `basic`; `<font color>` where `\c` are if `colors`.
+    /// Tags left open are closed at the end.
+    /// Must be called before `cleanse_text()`, which removes overrides.
+    pub(super) fn html_tags(&mut self, basic: bool, colors: bool, styles: &[Style]) {
+        let find = |name: &str| styles.iter().find(|s| s.name == name);
+        let style = if basic {
+            style_tags(find(self.style))
+        } else {
+            vec![]
+        };
+        if style.is_empty() && !self.text.contains('{') {
+            return;
+        }
+        let mut text = String::with_capacity(self.text.len());
+        let mut open: Vec<&str> = Vec::new();
+        for html in style {
+            open.push(html);
+            text += &format!("<{}>", html);
+        }
+        let mut rest: &str = &self.text;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map_or(rest.len(), |i| start + i + 1);
+            let block = &rest[start..end];
+            text += &rest[..end];
+            for tag in block.trim_matches(&['{', '}'][..]).split('\\') {
+                // \r or \rStyle resets all, to the line's style or that one
+                if tag.starts_with('r') && !tag.starts_with("rnd") {
+                    while let Some(html) = open.pop() {
+                        text += &format!("</{}>", html);
+                    }
+                    if basic {
+                        let name = if tag.len() > 1 { &tag[1..] } else { self.style };
+                        for html in style_tags(find(name)) {
+                            open.push(html);
+                            text += &format!("<{}>", html);
+                        }
+                    }
+                    continue;
+                }
+                if let Some(color) = color(tag).filter(|_| colors) {
+                    if open.contains(&"font") {
+                        open.retain(|h| *h != "font");
+                        text += "</font>";
+                    }
+                    if let Some(color) = color {
+                        open.push("font");
+                        text += &format!("<font color=\"{}\">", color);
+                    }
+                    continue;
+                }
+                for (ass, html) in BASIC.iter().filter(|_| basic) {
+                    match switch(tag, ass) {
+                        Some(true) if !open.contains(html) => {
+                            open.push(html);
+                            text += &format!("<{}>", html);
+                        }
+                        Some(false) if open.contains(html) => {
+                            open.retain(|h| h != html);
+                            text += &format!("</{}>", html);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            rest = &rest[end..];
+        }
+        text += rest;
+        while let Some(html) = open.pop() {
+            text += &format!("</{}>", html);
+        }
+        self.text = text.into();
+    }
+}
@@ -1,17 +1,59 @@
-use super::{ass_to_srt, Centisec, Dialogue};
+use super::{
+    ass_to_srt, bilingual,
+    chapter::ChapterFormat,
+    concat_to_srt, convert,
+    diff::{compare, DiffSummary},
+    effect::Effects,
+    filter::parse_patterns,
+    fonts::extract_fonts,
+    format::Format,
+    fps_fraction,
+    rtl::Bidi,
+    style::{parse_style_colors, parse_styles},
+    timeline::timeline,
+    wrap::{wrap, LineBreak},
+    Centisec, Dialogue, Options, Rounding,
+};
 
 #[test]
 fn test_cleanse_text() {
     let mut d = Dialogue {
         start: Centisec(0),
         end: Centisec(0),
-        effect: false,
+        style: "",
+        actor: "",
+        margins: [0; 3],
+        layout: None,
+        layer: 0,
+        height: 0,
+        sign: false,
+        effect: None,
+        comment: false,
         text: r"some{\fad(2,5)\p1\alpha&5}few{\p2}draw{\p0}{\b0\test}text{\b1}{\p0}\Nline".into(),
     };
-    d.cleanse_text();
+    d.cleanse_text(LineBreak::Newline, LineBreak::Newline);
     assert_eq!("sometext\r\nline", d.text);
 }
 
+#[test]
+fn test_line_breaks() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,one\Ntwo\nthree
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options::default();
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("\r\none\r\ntwo\r\nthree\r\n"));
+    opts.soft_breaks = LineBreak::Space;
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("\r\none\r\ntwo three\r\n"));
+    opts.hard_breaks = LineBreak::Keep;
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("\r\none\\Ntwo three\r\n"));
+}
+
 #[test]
 fn test_ass_to_srt() {
     let ass = r#"
@@ -35,6 +77,1329 @@ Something...\r\n\r\n\
 00:04:01,000 --> 00:04:02,000\r\n\
 sometext\r\n\r\n";
     let conv = |s| Some(s);
-    let result = ass_to_srt(ass, true, Some(conv)).unwrap();
+    let opts = Options {
+        no_effect: true,
+        ..Default::default()
+    };
+    let result = ass_to_srt(ass, &opts, Some(conv)).unwrap();
     assert_eq!(result, srt);
 }
+
+#[test]
+fn test_ssa_events() {
+    let ssa = r#"
+[Script Info]
+ScriptType: v4.00
+
+[V4 Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, TertiaryColour, BackColour, Bold, Italic, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, AlphaLevel, Encoding
+Style: Default,Arial,20,16777215,65535,65535,-2147483640,-1,0,1,3,0,2,30,30,30,0,0
+
+[Events]
+Format: Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: Marked=0,0:00:01.00,0:00:02.00,Default,,0000,0000,0000,,one, two
+"#;
+    let conv = |s| Some(s);
+    let srt = ass_to_srt(ssa, &Options::default(), Some(conv)).unwrap();
+    assert_eq!(
+        srt,
+        "1\r\n00:00:01,000 --> 00:00:02,000\r\none, two\r\n\r\n"
+    );
+}
+
+#[test]
+fn test_line_endings() {
+    let ass = "[Events]\rFormat: Start, End, Style, Text\r\
+               Dialogue: 0:00:01.00,0:00:02.00,Default,one\r\
+               Dialogue: 0:00:03.00,0:00:04.00,Default,two\r";
+    let conv = |s| Some(s);
+    let (srt, meta) = convert(ass, &Options::default(), Some(conv)).unwrap();
+    assert_eq!(meta.dialogues, 2);
+    assert!(srt.ends_with("00:00:03,000 --> 00:00:04,000\r\ntwo\r\n\r\n"));
+    assert_eq!(meta.warnings, ["old Mac (CR) line endings converted"]);
+}
+
+#[test]
+fn test_trim() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,main,a,0,0,0,,before
+Dialogue: 0:00:09.00,0:00:11.00,main,a,0,0,0,,across
+Dialogue: 0:00:12.00,0:00:13.00,main,a,0,0,0,,within
+Dialogue: 0:00:20.00,0:00:21.00,main,a,0,0,0,,after
+"#;
+    let srt = "\
+1\r\n\
+00:00:00,000 --> 00:00:01,000\r\n\
+across\r\n\r\n\
+2\r\n\
+00:00:02,000 --> 00:00:03,000\r\n\
+within\r\n\r\n";
+    let conv = |s| Some(s);
+    let opts = Options {
+        trim_start_secs: Some(10.0),
+        trim_end_secs: Some(15.0),
+        trim_rebase: true,
+        ..Default::default()
+    };
+    let result = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(result, srt);
+}
+
+#[test]
+fn test_concat() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,main,a,0,0,0,,one
+Dialogue: 0:00:03.00,0:00:04.00,main,a,0,0,0,,two
+"#;
+    let srt = "\
+1\r\n\
+00:00:01,000 --> 00:00:02,000\r\n\
+one\r\n\r\n\
+2\r\n\
+00:00:03,000 --> 00:00:04,000\r\n\
+two\r\n\r\n\
+3\r\n\
+00:00:05,000 --> 00:00:06,000\r\n\
+one\r\n\r\n\
+4\r\n\
+00:00:07,000 --> 00:00:08,000\r\n\
+two\r\n\r\n\
+5\r\n\
+00:01:01,000 --> 00:01:02,000\r\n\
+one\r\n\r\n\
+6\r\n\
+00:01:03,000 --> 00:01:04,000\r\n\
+two\r\n\r\n";
+    let conv = |s| Some(s);
+    let opts = Options::default();
+    let offsets = [Some(0.0), Some(4.0), Some(60.0)];
+    let result = concat_to_srt(&[ass, ass, ass], &offsets, &opts, Some(conv));
+    assert_eq!(result.unwrap().0, srt);
+    // a blank line follows the previous part, the next offset still applies
+    let conv = |s| Some(s);
+    let offsets = [Some(0.0), None, Some(60.0)];
+    let result = concat_to_srt(&[ass, ass, ass], &offsets, &opts, Some(conv));
+    assert_eq!(result.unwrap().0, srt);
+    let conv = |s| Some(s);
+    let (result, _) = concat_to_srt(&[ass, ass], &[], &opts, Some(conv)).unwrap();
+    assert!(result.contains("00:00:05,000 --> 00:00:06,000"));
+}
+
+#[test]
+fn test_remap_cuts() {
+    let cuts = [
+        (Centisec(1000), Centisec(2000)),
+        (Centisec(3000), Centisec(3500)),
+    ];
+    let remap = |t| {
+        let mut t = Centisec(t);
+        t.remap(&cuts);
+        t.0
+    };
+    assert_eq!(remap(500), 500);
+    assert_eq!(remap(1500), 1000);
+    assert_eq!(remap(2500), 1500);
+    assert_eq!(remap(3200), 2000);
+    assert_eq!(remap(4000), 2500);
+}
+
+#[test]
+fn test_retime_anchors() {
+    let retime = |anchors: &[(f32, f32)], t| {
+        let mut t = Centisec(t);
+        t.retime(anchors, Rounding::Nearest);
+        t.0
+    };
+    assert_eq!(retime(&[], 1000), 1000);
+    assert_eq!(retime(&[(10.0, 12.0)], 500), 700);
+    let anchors = [(10.0, 10.0), (20.0, 21.0), (30.0, 31.0)];
+    assert_eq!(retime(&anchors, 1500), 1550);
+    assert_eq!(retime(&anchors, 2500), 2600);
+    // extend first and last segment
+    assert_eq!(retime(&anchors, 0), 0);
+    assert_eq!(retime(&anchors, 4000), 4100);
+}
+
+#[test]
+fn test_fps_rescale() {
+    assert_eq!(fps_fraction(23.976), (24000, 1001));
+    assert_eq!(fps_fraction(29.97), (30000, 1001));
+    assert_eq!(fps_fraction(25.0), (25000, 1000));
+    assert_eq!(fps_fraction(24.0), (24000, 1000));
+    let rescale = |from, to, t| {
+        let mut t = Centisec(t);
+        t.rescale(fps_fraction(from), fps_fraction(to), Rounding::Nearest);
+        t.0
+    };
+    assert_eq!(rescale(24.0, 25.0, 2500), 2400);
+    // 1 hour of NTSC film sped up to PAL
+    assert_eq!(rescale(23.976, 25.0, 360_000), 345_255);
+    assert_eq!(rescale(25.0, 23.976, 345_255), 360_000);
+}
+
+#[test]
+fn test_rounding() {
+    let srt = "1\r\n00:00:01,234 --> 00:00:02,345\r\ntext\r\n";
+    let timing = |rounding| {
+        let opts = Options {
+            rounding,
+            ..Default::default()
+        };
+        let conv = |s| Some(s);
+        let srt = ass_to_srt(srt, &opts, Some(conv)).unwrap();
+        srt.lines().nth(1).unwrap().to_owned()
+    };
+    assert_eq!(timing(Rounding::Nearest), "00:00:01,230 --> 00:00:02,350");
+    assert_eq!(timing(Rounding::Floor), "00:00:01,230 --> 00:00:02,340");
+    assert_eq!(timing(Rounding::Ceil), "00:00:01,240 --> 00:00:02,350");
+    // f32 noise doesn't push whole centisecs up or down
+    let add = |secs, rounding| {
+        let mut t = Centisec(100);
+        t.add_secs(secs, rounding);
+        t.0
+    };
+    assert_eq!(add(0.1, Rounding::Ceil), 110);
+    assert_eq!(add(0.3, Rounding::Floor), 130);
+    assert_eq!(add(0.125, Rounding::Floor), 112);
+    assert_eq!(add(0.125, Rounding::Ceil), 113);
+}
+
+#[test]
+fn test_min_duration() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:01.10,Default,,0,0,0,,flash
+Dialogue: 0:00:01.40,0:00:01.50,Default,,0,0,0,,next
+Dialogue: 0:00:01.40,0:00:01.60,Default,,0,0,0,,along
+Dialogue: 0:00:05.00,0:00:08.00,Default,,0,0,0,,long
+"#;
+    let opts = Options {
+        min_duration_secs: 0.7,
+        ..Default::default()
+    };
+    let cues = timeline(ass, &opts).unwrap();
+    let times: Vec<_> = cues.iter().map(|c| (c.start, c.end)).collect();
+    assert_eq!(times, [(1.0, 1.4), (1.4, 2.1), (1.4, 2.1), (5.0, 8.0)]);
+}
+
+#[test]
+fn test_snap_frames() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.01,0:00:02.01,Default,,0,0,0,,touching
+Dialogue: 0:00:02.00,0:00:03.00,Default,,0,0,0,,next
+Dialogue: 0:00:05.00,0:00:05.01,Default,,0,0,0,,flash
+Dialogue: 0:00:10.00,0:00:12.00,Default,,0,0,0,,overlapping
+Dialogue: 0:00:11.00,0:00:13.00,Default,,0,0,0,,the next
+"#;
+    let opts = Options {
+        snap_fps: 25.0,
+        ..Default::default()
+    };
+    let cues = timeline(ass, &opts).unwrap();
+    let times: Vec<_> = cues.iter().map(|c| (c.start, c.end)).collect();
+    assert_eq!(
+        times,
+        [
+            (1.0, 1.96),
+            (2.0, 3.0),
+            (5.0, 5.04),
+            (10.0, 10.96),
+            (11.0, 13.0)
+        ]
+    );
+}
+
+#[test]
+fn test_merge_duplicates() {
+    let ass = r#"
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0,0:00:01.00,0:00:03.00,Default,,0,0,0,,hello
+Dialogue: 1,0:00:01.00,0:00:03.00,Default,,0,0,0,,hello
+Dialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,hello
+Dialogue: 0,0:00:02.00,0:00:05.00,Default,,0,0,0,,world
+Dialogue: 0,0:00:06.00,0:00:07.00,Default,,0,0,0,,hello
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        merge_duplicates: true,
+        ..Default::default()
+    };
+    let (srt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(meta.dialogues, 3);
+    assert!(srt.contains("00:00:01,000 --> 00:00:04,000\r\nhello\r\n"));
+    assert!(srt.contains("00:00:06,000 --> 00:00:07,000\r\nhello\r\n"));
+}
+
+#[test]
+fn test_wrap() {
+    assert_eq!(wrap("short line", 20), "short line");
+    assert_eq!(
+        wrap("I don't think we should go there tonight", 16),
+        "I don't think we\r\nshould go there\r\ntonight"
+    );
+    assert_eq!(
+        wrap("<i>I don't think</i> we should", 13),
+        "<i>I don't think</i>\r\nwe should"
+    );
+    assert_eq!(
+        wrap("今天天气很好，我们去公园吧。", 7),
+        "今天天气很好，\r\n我们去公园吧。"
+    );
+    assert_eq!(
+        wrap("我买了「新的」iPhone手机", 4),
+        "我买了\r\n「新的」\r\niPhone\r\n手机"
+    );
+}
+
+#[test]
+fn test_max_lines() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:04.00,Default,,0,0,0,,one two three four five six seven eight nine
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        max_chars_per_line: 8,
+        max_lines: 2,
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("00:00:01,000 --> 00:00:01,940\r\none two\r\nthree\r\n"));
+    assert!(srt.contains("00:00:01,940 --> 00:00:02,890\r\nfour\r\nfive six\r\n"));
+}
+
+#[test]
+fn test_style_filters() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,hello
+Dialogue: 0:00:01.00,0:00:02.00,Default-alt,,0,0,0,,flashback
+Dialogue: 0:00:03.00,0:00:04.00,OP Romaji,,0,0,0,,la la
+Dialogue: 0:00:03.00,0:00:04.00,Sign,,0,0,0,,Station
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        exclude_styles: parse_patterns("op*, SIGN"),
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("hello") && srt.contains("flashback"));
+    assert!(!srt.contains("la la") && !srt.contains("Station"));
+
+    opts.include_styles = parse_patterns("Default\nDefault-???");
+    opts.exclude_styles = vec![];
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("hello") && srt.contains("flashback"));
+    assert!(!srt.contains("la la") && !srt.contains("Station"));
+}
+
+#[test]
+fn test_actor_filters() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,Alice,0,0,0,,hello
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,unnamed
+Dialogue: 0:00:03.00,0:00:04.00,Default,sign,0,0,0,,Station
+Dialogue: 0:00:03.00,0:00:04.00,Default,TL note,0,0,0,,a pun
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        exclude_actors: parse_patterns("Sign, TL*"),
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("hello") && srt.contains("unnamed"));
+    assert!(!srt.contains("Station") && !srt.contains("a pun"));
+
+    opts.include_actors = parse_patterns("alice");
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("hello") && !srt.contains("unnamed"));
+}
+
+#[test]
+fn test_layer_filters() {
+    let ass = r#"
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,bottom
+Dialogue: 1,0:00:01.00,0:00:02.00,Default,,0,0,0,,middle
+Dialogue: 5,0:00:03.00,0:00:04.00,Default,,0,0,0,,top
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        min_layer: Some(1),
+        max_layer: Some(4),
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("middle"));
+    assert!(!srt.contains("bottom") && !srt.contains("top"));
+}
+
+#[test]
+fn test_karaoke_lines() {
+    let ass = r#"
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Comment: 0,0:00:01.00,0:00:03.00,OP,,0,0,0,karaoke,{\k50}ko{\k50}no {\k100}michi
+Dialogue: 0,0:00:01.00,0:00:01.50,OP,,0,0,0,fx,{\pos(100,20)}ko
+Dialogue: 0,0:00:01.50,0:00:02.00,OP,,0,0,0,fx,{\pos(120,20)}no
+Dialogue: 0,0:00:04.00,0:00:05.00,Default,,0,0,0,,hello
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        karaoke_lines: true,
+        ..Default::default()
+    };
+    let (srt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(meta.dialogues, 2);
+    assert!(srt.contains("00:00:01,000 --> 00:00:03,000\r\nkono michi\r\n"));
+}
+
+#[test]
+fn test_drawings() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:03.00,Default,,0,0,0,,{\an7\p1}m 0 0 l 100 0 100 100{\p0}Box
+Dialogue: 0:00:01.00,0:00:03.00,Default,,0,0,0,,hello
+Dialogue: 0:00:01.00,0:00:03.00,Default,,0,0,0,,{\pos(1,2)}{\p2}m 0 0 b 1 1 2 2 3 3
+"#;
+    let conv = |s| Some(s);
+    let (srt, meta) = convert(ass, &Options::default(), Some(conv)).unwrap();
+    // text around a drawing is kept
+    assert_eq!(meta.dialogues, 2);
+    assert_eq!(meta.drawings, 1);
+    assert!(srt.contains("\r\nBox\r\n") && !srt.contains("m 0 0"));
+
+    // only those within the trim range
+    let opts = Options {
+        trim_end_secs: Some(0.5),
+        ..Default::default()
+    };
+    let (_, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(meta.drawings, 0);
+}
+
+#[test]
+fn test_reading_speed() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,ten chars!
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,,twenty-five characters\Nhere
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        max_cps: 15.0,
+        ..Default::default()
+    };
+    let (_, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(meta.fast_cues, 1);
+    assert_eq!(meta.max_cps, 26.0);
+    assert_eq!(meta.warnings.len(), 1);
+}
+
+#[test]
+fn test_keep_order() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:05.00,0:00:06.00,Default,,0,0,0,,second
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,first
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options::default();
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.starts_with("1\r\n00:00:01,000 --> 00:00:02,000\r\nfirst\r\n"));
+    opts.keep_order = true;
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.starts_with("1\r\n00:00:05,000 --> 00:00:06,000\r\nsecond\r\n"));
+}
+
+#[test]
+fn test_comment_cues() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,hello
+Comment: 0:00:01.00,0:00:02.00,Default,,0,0,0,,TL: a greeting
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        comment_cues: true,
+        ..Default::default()
+    };
+    let (srt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(meta.dialogues, 2);
+    assert!(srt.contains("\r\n[TL: a greeting]\r\n"));
+}
+
+#[test]
+fn test_bilingual() {
+    let zh = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:03.00,Default,,0,0,0,,你好
+Dialogue: 0:00:05.00,0:00:06.00,Default,,0,0,0,,再见
+"#;
+    let en = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.10,0:00:02.00,Default,,0,0,0,,Hello.
+Dialogue: 0:00:02.00,0:00:03.10,Default,,0,0,0,,How are you?
+Dialogue: 0:00:08.00,0:00:09.00,Default,,0,0,0,,Bye.
+"#;
+    let conv = |s| Some(s);
+    let (srt, meta) = bilingual::merge(zh, en, &Options::default(), Some(conv)).unwrap();
+    assert_eq!(meta.dialogues, 3);
+    assert!(
+        srt.starts_with("1\r\n00:00:01,000 --> 00:00:03,000\r\n你好\r\nHello. How are you?\r\n")
+    );
+    assert!(srt.contains("00:00:05,000 --> 00:00:06,000\r\n再见\r\n\r\n"));
+    assert!(srt.ends_with("00:00:08,000 --> 00:00:09,000\r\nBye.\r\n\r\n"));
+}
+
+#[test]
+fn test_compare() {
+    let a = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,main,a,0,0,0,,same
+Dialogue: 0:00:03.00,0:00:04.00,main,a,0,0,0,,old
+Dialogue: 0:00:05.00,0:00:06.00,main,a,0,0,0,,removed
+"#;
+    let b = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,main,a,0,0,0,,same
+Dialogue: 0:00:03.10,0:00:04.00,main,a,0,0,0,,new
+Dialogue: 0:00:07.00,0:00:08.00,main,a,0,0,0,,added
+"#;
+    let conv = |s| Some(s);
+    let (report, summary) = compare(a, b, &Options::default(), Some(conv)).unwrap();
+    let expected = DiffSummary {
+        added: 1,
+        removed: 1,
+        changed: 1,
+        unchanged: 1,
+    };
+    assert_eq!(summary, expected);
+    assert!(report.contains(
+        "~ [00:00:03,000 --> 00:00:04,000] old\r\n  [00:00:03,100 --> 00:00:04,000] new"
+    ));
+}
+
+#[test]
+fn test_untranslated() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,main,a,0,0,0,,你好，世界
+Dialogue: 0:00:03.00,0:00:04.00,main,a,0,0,0,,OK
+Dialogue: 0:00:05.00,0:00:06.00,main,a,0,0,0,,Where are you going?
+Dialogue: 0:00:07.00,0:00:08.00,main,a,0,0,0,,我们去 Tokyo 吧
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        check_untranslated: true,
+        ..Default::default()
+    };
+    let (_, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(meta.dialogues, 4);
+    assert_eq!(meta.duration_secs, 4.0);
+    assert_eq!(meta.script, Some("CJK"));
+    assert_eq!(
+        meta.warnings,
+        vec!["untranslated? #3 00:00:05,000: Where are you going?"]
+    );
+}
+
+#[test]
+fn test_coverage() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,你好 OK
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,,𠀀𠀀 😀
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        check_coverage: true,
+        ..Default::default()
+    };
+    let (_, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        meta.blocks,
+        [
+            "CJK Unified Ideographs",
+            "Basic Latin",
+            "CJK Extension B+",
+            "Emoji & Pictographs"
+        ]
+    );
+    assert_eq!(
+        meta.warnings,
+        [
+            "may not render: 2 CJK Extension B+ char(s) like 𠀀, first at #2 00:00:03,000",
+            "may not render: 1 Emoji & Pictographs char(s) like 😀, first at #2 00:00:03,000",
+        ]
+    );
+}
+
+#[test]
+fn test_style_colors() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Alice,a,0,0,0,,hi
+Dialogue: 0:00:03.00,0:00:04.00,Bob,b,0,0,0,,hello
+Dialogue: 0:00:05.00,0:00:06.00,Default,a,0,0,0,,bye
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        style_colors: parse_style_colors("Alice = #ff0000\nBob=none\n"),
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("\r\n<font color=\"#ff0000\">hi</font>\r\n"));
+    assert!(srt.contains("\r\nhello\r\n"));
+    assert!(srt.contains("\r\nbye\r\n"));
+}
+
+#[test]
+fn test_vtt_styles() {
+    let ass = r#"
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut
+Style: Sign 1,Arial,20,&H000000FF,&H00FFFFFF,&H00000000,&H00000000,-1,0,0,0
+
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Sign 1,a,0,0,0,,a < b
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::WebVtt,
+        vtt_styles: true,
+        ..Default::default()
+    };
+    let (vtt, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(vtt.starts_with("WEBVTT\r\n\r\nSTYLE\r\n::cue(.Sign_1) {\r\n"));
+    assert!(vtt.contains("  color: #ff0000;\r\n  font-weight: bold;\r\n}\r\n"));
+    assert!(vtt.contains("00:00:01.000 --> 00:00:02.000\r\n<c.Sign_1>a &lt; b</c>\r\n"));
+
+    let opts = Options {
+        format: Format::WebVtt,
+        vtt_classes: true,
+        ..Default::default()
+    };
+    let (vtt, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(!vtt.contains("STYLE"));
+    assert!(vtt.contains("<c.Sign_1>a &lt; b</c>"));
+}
+
+#[test]
+fn test_microdvd() {
+    let ass = r#"
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, Bold, Italic
+Style: Thought,Arial,20,0,-1
+
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,one\Ntwo
+Dialogue: 0:00:03.00,0:00:04.50,Thought,,0,0,0,,hmm
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::MicroDvd,
+        fps: 25.0,
+        ..Default::default()
+    };
+    let (sub, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(sub, "{1}{1}25\r\n{25}{50}one|two\r\n{75}{113}{Y:i}hmm\r\n");
+}
+
+#[test]
+fn test_lrc() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,,0,0,0,,one\Ntwo
+Dialogue: 0:00:02.50,0:00:04.00,Default,,0,0,0,,three
+Dialogue: 0:01:10.25,0:01:12.00,Default,,0,0,0,,four
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Lrc,
+        ..Default::default()
+    };
+    let (lrc, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        lrc,
+        "[00:01.00]one two\r\n[00:02.50]three\r\n[00:04.00]\r\n\
+         [01:10.25]four\r\n[01:12.00]\r\n"
+    );
+}
+
+#[test]
+fn test_ttml() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,,0,0,0,,a & b
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Ttml,
+        ..Default::default()
+    };
+    let (ttml, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(ttml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n<tt "));
+    assert!(ttml.contains(
+        "<p begin=\"00:00:01.000\" end=\"00:00:02.500\" region=\"bottom\">a &amp; b</p>\r\n"
+    ));
+    assert!(ttml.ends_with("</div>\r\n</body>\r\n</tt>\r\n"));
+}
+
+#[test]
+fn test_sbv() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,,0,0,0,,one
+Dialogue: 1:02:03.40,1:02:05.00,Default,,0,0,0,,two
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Sbv,
+        ..Default::default()
+    };
+    let (sbv, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        sbv,
+        "0:00:01.000,0:00:02.500\r\none\r\n\r\n1:02:03.400,1:02:05.000\r\ntwo\r\n\r\n"
+    );
+}
+
+#[test]
+fn test_sami() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,,0,0,0,,안녕하세요
+Dialogue: 0:00:02.50,0:00:04.00,Default,,0,0,0,,<잘 가세요>
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Sami,
+        ..Default::default()
+    };
+    let (sami, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(sami.contains(".KRCC { Name:Korean; lang:ko-KR; SAMIType:CC; }"));
+    assert!(sami.ends_with(
+        "<SYNC Start=1000><P Class=KRCC>안녕하세요\r\n\
+         <SYNC Start=2500><P Class=KRCC>&lt;잘 가세요&gt;\r\n\
+         <SYNC Start=4000><P Class=KRCC>&nbsp;\r\n\
+         </BODY>\r\n</SAMI>\r\n"
+    ));
+}
+
+#[test]
+fn test_transcript() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,Alice,0,0,0,,hello
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,,world
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        format: Format::Transcript,
+        ..Default::default()
+    };
+    let (txt, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(txt, "hello\r\nworld\r\n");
+    opts.transcript_actors = true;
+    let (txt, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(txt, "Alice: hello\r\nworld\r\n");
+}
+
+#[test]
+fn test_json() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,Alice,0,0,0,,"hi"
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Json,
+        ..Default::default()
+    };
+    let (json, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"start_ms":1000,"end_ms":2500,"text":"\"hi\"","style":"Default","actor":"Alice"}]"#
+    );
+}
+
+#[test]
+fn test_csv() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,Alice,0,0,0,,say "hi", bye
+Dialogue: 0:01:00.00,0:01:01.00,Default,,0,0,0,,plain
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Csv,
+        ..Default::default()
+    };
+    let (csv, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        csv,
+        "index,start,end,duration,style,actor,text\r\n\
+         1,00:00:01.000,00:00:02.500,1.50,Default,Alice,\"say \"\"hi\"\", bye\"\r\n\
+         2,00:01:00.000,00:01:01.000,1.00,Default,,plain\r\n"
+    );
+}
+
+#[test]
+fn test_clean_ass() {
+    let ass = "[Script Info]\r\n\
+               Title: x\r\n\
+               \r\n\
+               [Events]\r\n\
+               Format: Layer, Start, End, Style, Text\r\n\
+               Dialogue: 0,0:00:03.00,0:00:04.00,Default,b, {\\i1}b\r\n\
+               Dialogue: 0,0:00:01.00,0:00:02.00,Default,a\r\n\
+               \r\n\
+               [Fonts]\r\n\
+               fontname: a_0.ttf\r\n\
+               97*D\r\n";
+    let conv = |s: String| Some(s.to_uppercase());
+    let opts = Options {
+        format: Format::Ass,
+        ..Default::default()
+    };
+    let (out, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        out,
+        "[Script Info]\r\n\
+         Title: x\r\n\
+         \r\n\
+         [Events]\r\n\
+         Format: Layer, Start, End, Style, Text\r\n\
+         Dialogue: 0,0:00:01.00,0:00:02.00,Default,A\r\n\
+         Dialogue: 0,0:00:03.00,0:00:04.00,Default,B, {\\i1}B\r\n"
+    );
+}
+
+#[test]
+fn test_srt_input() {
+    let srt = "1\r\n00:00:03,000 --> 00:00:04,000\r\nsecond\r\n\r\n\
+               2\r\n00:00:01,000 --> 00:00:02,500\r\n<i>one</i>\r\ntwo\r\n";
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Ass,
+        ..Default::default()
+    };
+    let (ass, _) = convert(srt, &opts, Some(conv)).unwrap();
+    assert!(ass.starts_with("[Script Info]\r\n"));
+    assert!(ass.ends_with(
+        "Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\i1}one{\\i0}\\Ntwo\r\n\
+         Dialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,second\r\n"
+    ));
+}
+
+#[test]
+fn test_srt_to_srt() {
+    let srt = "1\n00:00:01,000 --> 00:00:02,000\n<i>汉字</i>\n";
+    let conv = |s: String| Some(s.replace("汉", "漢"));
+    let srt = ass_to_srt(srt, &Options::default(), Some(conv)).unwrap();
+    assert_eq!(
+        srt,
+        "1\r\n00:00:01,000 --> 00:00:02,000\r\n<i>漢字</i>\r\n\r\n"
+    );
+}
+
+#[test]
+fn test_vtt_input() {
+    let vtt = "WEBVTT\n\nNOTE made by hand\n\n\
+               intro\n00:01.000 --> 00:02.500 align:start line:10%\n<b>A &amp; B</b>\n\n\
+               01:00:00.000 --> 01:00:01.000\nlast\n";
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Ass,
+        ..Default::default()
+    };
+    let (ass, _) = convert(vtt, &opts, Some(conv)).unwrap();
+    assert!(ass.ends_with(
+        "Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\b1}A & B{\\b0}\r\n\
+         Dialogue: 0,1:00:00.00,1:00:01.00,Default,,0,0,0,,last\r\n"
+    ));
+}
+
+#[test]
+fn test_lrc_words() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:03.00,Default,,0,0,0,,{\k50}Hel{\K30}lo {\kf120}world
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        format: Format::Lrc,
+        lrc_words: true,
+        offset_secs: 1.0,
+        ..Default::default()
+    };
+    let (lrc, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        lrc,
+        "[00:02.00]<00:02.00>Hel<00:02.50>lo <00:02.80>world\r\n[00:04.00]\r\n"
+    );
+    opts.lrc_words = false;
+    let (lrc, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(lrc, "[00:02.00]Hello world\r\n[00:04.00]\r\n");
+}
+
+#[test]
+fn test_labels() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.50,Default,,0,0,0,,hello
+Dialogue: 1:00:00.00,1:00:01.05,Default,,0,0,0,,bye
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::Labels,
+        ..Default::default()
+    };
+    let (labels, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        labels,
+        "1.000000\t2.500000\thello\n3600.000000\t3601.050000\tbye\n"
+    );
+}
+
+#[test]
+fn test_speakers() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,John,0,0,0,,hello
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,,bye
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        speaker_sep: Some(": ".into()),
+        speaker_upper: true,
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.starts_with("1\r\n00:00:01,000 --> 00:00:02,000\r\nJOHN: hello\r\n"));
+    assert!(srt.ends_with("00:00:04,000\r\nbye\r\n\r\n"));
+    opts.speaker_upper = false;
+    opts.speaker_sep = Some(" - ".into());
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("John - hello"));
+}
+
+#[test]
+fn test_html_tags() {
+    let mut d = Dialogue {
+        start: Centisec(0),
+        end: Centisec(0),
+        style: "",
+        actor: "",
+        margins: [0; 3],
+        layout: None,
+        layer: 0,
+        height: 0,
+        sign: false,
+        effect: None,
+        comment: false,
+        text: r"{\i1\bord2}a{\b700}b{\i0\blur1}c{\u1}d{\r}e{\b1}f".into(),
+    };
+    d.html_tags(true, false, &[]);
+    assert_eq!(
+        d.text,
+        r"{\i1\bord2}<i>a{\b700}<b>b{\i0\blur1}</i>c{\u1}<u>d{\r}</u></b>e{\b1}<b>f</b>"
+    );
+
+    d.text = r"{\c&H0000FF&}red{\1c&HFF8000&\i1}blue{\c}plain{\clip(0,0,1,1)}".into();
+    d.html_tags(false, true, &[]);
+    assert_eq!(
+        d.text,
+        concat!(
+            r##"{\c&H0000FF&}<font color="#ff0000">red{\1c&HFF8000&\i1}</font>"##,
+            r##"<font color="#0080ff">blue{\c}</font>plain{\clip(0,0,1,1)}"##
+        )
+    );
+
+    let styles = parse_styles(
+        "[V4+ Styles]\nFormat: Name, Bold, Italic\n\
+         Style: Thought,0,-1\nStyle: Loud,1,0\n",
+    );
+    d.style = "Thought";
+    d.text = "plain".into();
+    d.html_tags(true, false, &styles);
+    assert_eq!(d.text, "<i>plain</i>");
+    d.text = r"a{\i0}b{\r}c{\rLoud}d".into();
+    d.html_tags(true, false, &styles);
+    assert_eq!(d.text, r"<i>a{\i0}</i>b{\r}<i>c{\rLoud}</i><b>d</b>");
+    // only with basic tags kept
+    d.text = "plain".into();
+    d.html_tags(false, true, &styles);
+    assert_eq!(d.text, "plain");
+}
+
+#[test]
+fn test_vtt_regions() {
+    let ass = r#"
+[Script Info]
+PlayResX: 1000
+PlayResY: 500
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, Alignment, MarginL, MarginR, MarginV
+Style: Default,Arial,20,2,100,100,50
+
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,a,0,0,0,,bottom\Ntwo lines
+Dialogue: 0:00:03.00,0:00:04.00,Default,a,0,0,0,,{\an8}top
+Dialogue: 0:00:05.00,0:00:06.00,Default,a,0,0,0,,bottom again
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::WebVtt,
+        vtt_regions: true,
+        ..Default::default()
+    };
+    let (vtt, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(vtt.contains(
+        "REGION\r\nid:bottom\r\nwidth:80%\r\nlines:2\r\n\
+         regionanchor:0%,100%\r\nviewportanchor:10%,90%\r\n\r\n"
+    ));
+    assert!(vtt.contains("regionanchor:0%,0%\r\nviewportanchor:10%,10%\r\n"));
+    assert!(vtt.contains("00:00:03.000 --> 00:00:04.000 region:top\r\ntop\r\n"));
+    assert!(vtt.contains("00:00:05.000 --> 00:00:06.000 region:bottom\r\n"));
+}
+
+#[test]
+fn test_vtt_cue_settings() {
+    let ass = r#"
+[Script Info]
+PlayResX: 1000
+PlayResY: 500
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, Alignment, MarginL, MarginR, MarginV
+Style: Default,Arial,20,2,100,100,50
+
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,bottom
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,,{\an8}top
+Dialogue: 0:00:05.00,0:00:06.00,Default,,0,0,0,,{\an4}left
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        format: Format::WebVtt,
+        ..Default::default()
+    };
+    let (vtt, _) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(!vtt.contains("REGION"));
+    assert!(vtt.contains("00:00:01.000 --> 00:00:02.000\r\n"));
+    assert!(vtt.contains("00:00:03.000 --> 00:00:04.000 line:10%\r\n"));
+    assert!(vtt.contains("00:00:05.000 --> 00:00:06.000 line:50%,center align:left\r\n"));
+}
+
+#[test]
+fn test_simultaneous_order() {
+    let ass = r#"
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 1,0:00:01.00,0:00:02.00,Default,,0,0,0,,layer 1
+Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\an8}sign
+Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,dialogue
+"#;
+    let conv = |s| Some(s);
+    let srt = ass_to_srt(ass, &Options::default(), Some(conv)).unwrap();
+    let order: Vec<_> = srt.lines().skip(2).step_by(4).collect();
+    assert_eq!(order, ["dialogue", "sign", "layer 1"]);
+}
+
+#[test]
+fn test_effects() {
+    let ass = r#"
+[Script Info]
+PlayResY: 500
+
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,Scroll up;400;100;20,credits
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,Banner;10,news
+Dialogue: 0:00:05.00,0:00:06.00,Default,,0,0,0,Karaoke,kara
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        no_effect: true,
+        ..Default::default()
+    };
+    let (srt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.is_empty());
+    assert!(meta.warnings.is_empty());
+
+    opts.effects = Effects::Warn;
+    let (srt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("credits") && srt.contains("news") && !srt.contains("kara"));
+    assert_eq!(meta.warnings.len(), 2);
+
+    opts.effects = Effects::Region;
+    opts.format = Format::WebVtt;
+    let (vtt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert!(meta.warnings.is_empty());
+    assert!(vtt.contains(
+        "width:100%\r\nlines:1\r\nregionanchor:0%,0%\r\nviewportanchor:0%,20%\r\nscroll:up\r\n"
+    ));
+    assert!(vtt.contains("00:00:01.000 --> 00:00:02.000 region:top\r\ncredits\r\n"));
+}
+
+#[test]
+fn test_strip_sdh() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,[door slams]
+Dialogue: 0:00:02.00,0:00:03.00,Default,,0,0,0,,(music)\NHello
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,,- Where? [gasps]\N- [whispers] Here.
+Dialogue: 0:00:04.00,0:00:05.00,Default,,0,0,0,,♪ la la ♪
+Dialogue: 0:00:05.00,0:00:06.00,Default,,0,0,0,,{\i1}[laughs]{\i0}\NNo way
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        strip_sdh: true,
+        keep_basic_tags: true,
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        srt,
+        "1\r\n00:00:02,000 --> 00:00:03,000\r\nHello\r\n\r\n\
+         2\r\n00:00:03,000 --> 00:00:04,000\r\n- Where?\r\n- Here.\r\n\r\n\
+         3\r\n00:00:05,000 --> 00:00:06,000\r\nNo way\r\n\r\n"
+    );
+}
+
+#[test]
+fn test_drop_signs() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:03.00,Default,,0,0,0,,hello there
+Dialogue: 0:00:01.00,0:00:03.00,Default,,0,0,0,,{\pos(100,200)}Shop
+Dialogue: 0:00:01.00,0:00:03.00,Signs,,0,0,0,,{\an8}Station
+Dialogue: 0:00:04.00,0:00:06.00,Default,,0,0,0,,{\an8}on top
+Dialogue: 0:00:04.00,0:00:06.00,Default,,0,0,0,,{\frz-10}Tilted
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        drop_signs: true,
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("hello there") && srt.contains("on top"));
+    assert!(!srt.contains("Shop") && !srt.contains("Station") && !srt.contains("Tilted"));
+
+    opts.drop_signs = false;
+    opts.only_signs = true;
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(!srt.contains("hello there") && !srt.contains("on top"));
+    assert!(srt.contains("Shop") && srt.contains("Station") && srt.contains("Tilted"));
+}
+
+#[test]
+fn test_rtl() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,שלום (OK) 3.14.
+Dialogue: 0:00:03.00,0:00:04.00,Default,,0,0,0,,Hello (OK).
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        bidi: Bidi::Logical,
+        ..Default::default()
+    };
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("\u{202b}שלום (OK\u{200f}) 3.14\u{200f}.\u{202c}\r\n"));
+    assert!(srt.contains("\r\nHello (OK).\r\n"));
+
+    opts.bidi = Bidi::Visual;
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.contains("\r\n.3.14 (OK) םולש\r\n"));
+    assert!(srt.contains("\r\nHello (OK).\r\n"));
+}
+
+#[test]
+fn test_forced() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:01:00.00,0:01:02.00,Default,,0,0,0,,Привет
+Dialogue: 0:12:00.00,0:12:02.00,Default,,0,0,0,,Пока
+"#;
+    let conv = |s| Some(s);
+    let (_, meta) = convert(ass, &Options::default(), Some(conv)).unwrap();
+    assert!(meta.forced);
+
+    let ass = ass.replace("0:12:", "0:01:");
+    let (_, meta) = convert(&ass, &Options::default(), Some(conv)).unwrap();
+    assert!(!meta.forced);
+    let opts = Options {
+        forced: true,
+        ..Default::default()
+    };
+    let (_, meta) = convert(&ass, &opts, Some(conv)).unwrap();
+    assert!(meta.forced);
+}
+
+#[test]
+fn test_timeline() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:01.00,0:00:05.00,Default,,0,0,0,,one
+Dialogue: 0:00:02.00,0:00:03.00,Default,,0,0,0,,two
+Dialogue: 0:00:04.50,0:00:04.80,Default,,0,0,0,,three
+Dialogue: 0:00:06.00,0:00:08.00,Default,,0,0,0,,four
+"#;
+    let cues = timeline(ass, &Options::default()).unwrap();
+    let flags: Vec<_> = cues.iter().map(|c| (c.overlap, c.short)).collect();
+    assert_eq!(
+        flags,
+        vec![(true, false), (true, false), (true, true), (false, false)]
+    );
+    assert_eq!(cues[3].start, 6.0);
+}
+
+#[test]
+fn test_comments() {
+    let ass = r#"
+[Script Info]
+; translated by someone
+Title: test
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Comment: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,pun --> see note
+Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,hello
+"#;
+    let conv = |s| Some(s);
+    let mut opts = Options {
+        format: Format::WebVtt,
+        comments: true,
+        ..Default::default()
+    };
+    let (vtt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(meta.dialogues, 1);
+    assert!(vtt.starts_with("WEBVTT\r\n\r\nNOTE translated by someone\r\n\r\n"));
+    assert!(vtt.contains("NOTE pun -> see note\r\n\r\n2\r\n00:00:01.000"));
+
+    opts.format = Format::Srt;
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.starts_with("1\r\n00:00:01,000 --> 00:00:02,000\r\n{# pun --> see note}\r\n"));
+
+    opts.comments = false;
+    let srt = ass_to_srt(ass, &opts, Some(conv)).unwrap();
+    assert!(srt.starts_with("1\r\n00:00:01,000 --> 00:00:02,000\r\nhello\r\n"));
+}
+
+#[test]
+fn test_chapters() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0:00:00.00,0:00:05.00,Chapter,,0,0,0,,Intro
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,hello
+Dialogue: 0:01:05.00,0:01:10.00,Chapter,,0,0,0,,Part\Ntwo
+Dialogue: 1:02:03.00,1:02:10.00,Chapter,,0,0,0,,End
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        chapter_style: Some("Chapter".into()),
+        ..Default::default()
+    };
+    let (_, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        meta.chapters.unwrap(),
+        "00:00 Intro\r\n01:05 Part two\r\n1:02:03 End\r\n"
+    );
+    assert!(meta.warnings.is_empty());
+}
+
+#[test]
+fn test_ogm_chapters() {
+    let ass = r#"
+[Events]
+Format: Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Comment: 0:00:00.00,0:00:00.00,Default,,0,0,0,,Opening
+Dialogue: 0:00:01.00,0:00:02.00,Default,,0,0,0,,hello
+Comment: 0:01:30.25,0:01:30.25,Default,,0,0,0,,Part A
+"#;
+    let conv = |s| Some(s);
+    let opts = Options {
+        chapter_comments: true,
+        chapter_format: ChapterFormat::Ogm,
+        ..Default::default()
+    };
+    let (srt, meta) = convert(ass, &opts, Some(conv)).unwrap();
+    assert_eq!(
+        meta.chapters.unwrap(),
+        "CHAPTER01=00:00:00.000\r\nCHAPTER01NAME=Opening\r\n\
+         CHAPTER02=00:01:30.250\r\nCHAPTER02NAME=Part A\r\n"
+    );
+    assert_eq!(meta.dialogues, 1);
+    assert!(!srt.contains("Opening"));
+}
+
+#[test]
+fn test_extract_fonts() {
+    let ass = r#"
+[Fonts]
+fontname: a_0.ttf
+97*D
+:'5
+
+fontname: ../b_0.ttf
+97*D
+
+[Events]
+"#;
+    let fonts = extract_fonts(ass);
+    assert_eq!(
+        fonts,
+        vec![
+            ("a_0.ttf".to_owned(), b"abcde".to_vec()),
+            ("b_0.ttf".to_owned(), b"abc".to_vec()),
+        ]
+    );
+}
@@ -0,0 +1,53 @@
+use super::{dialogues, read_script, Centisec, Options};
+
+/// shorter than this is hard to read
+const SHORT: Centisec = Centisec(70);
+
+/// Output time of a dialogue, flagged for timing problems.
+pub struct Cue {
+    pub start: f32,
+    pub end: f32,
+    /// on screen together with another one
+    pub overlap: bool,
+    pub short: bool,
+}
+
+/// Cues of the script as they would be written, in order of start.
+pub fn timeline(ass: &str, opts: &Options) -> Result<Vec<Cue>, &'static str> {
+    let (ass, _) = read_script(ass, opts);
+    let opts = &Options {
+        comments: false,
+        chapter_comments: false,
+        ..opts.clone()
+    };
+    let dialogues = dialogues(
+        &ass,
+        opts,
+        &mut None::<fn(String) -> Option<String>>,
+        &mut 0,
+    )?;
+    let mut cues: Vec<Cue> = dialogues
+        .iter()
+        .map(|d| Cue {
+            start: d.start.as_secs(),
+            end: d.end.as_secs(),
+            overlap: false,
+            short: d.end.0 - d.start.0 < SHORT.0,
+        })
+        .collect();
+    // the one ends last so far, overlapped by anything starts before that
+    let mut last: Option<(usize, Centisec)> = None;
+    for (i, d) in dialogues.iter().enumerate() {
+        if let Some((j, end)) = last {
+            if d.start < end {
+                cues[i].overlap = true;
+                cues[j].overlap = true;
+            }
+        }
+        match last {
+            Some((_, end)) if d.end <= end => (),
+            _ => last = Some((i, d.end)),
+        }
+    }
+    Ok(cues)
+}
@@ -0,0 +1,159 @@
+use super::{
+    qc::{script, Script},
+    Centisec, Dialogue,
+};
+use serde::Deserialize;
+
+/// What `\N` (hard) or `\n` (soft) line breaks of ASS become.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum LineBreak {
+    #[default]
+    Newline,
+    Space,
+    /// left as `\N` or `\n`, for players that render them
+    Keep,
+}
+
+impl LineBreak {
+    pub(super) fn replace(self, text: &str, tag: &str) -> String {
+        match self {
+            LineBreak::Newline => text.replace(tag, "\r\n"),
+            LineBreak::Space => text.replace(tag, " "),
+            LineBreak::Keep => text.to_owned(),
+        }
+    }
+}
+
+/// CJK punctuation a line shouldn't start with.
+const NO_START: &str =
+    "。，、．：；！？）」』】〕〉》’”…ー〜々ぁぃぅぇぉっゃゅょァィゥェォッャュョ,.!?:;)";
+/// and the ones it shouldn't end with.
+const NO_END: &str = "（「『【〔〈《‘“(";
+
+fn is_cjk(c: char) -> bool {
+    script(c) == Some(Script::Cjk) || matches!(c as u32, 0x3000..=0x303f | 0xff00..=0xffef)
+}
+
+/// Chars outside of `<...>` tags.
+pub(super) fn width(line: &str) -> usize {
+    let mut in_tag = false;
+    line.chars()
+        .filter(|&c| {
+            let visible = !in_tag && c != '<';
+            in_tag = if in_tag { c != '>' } else { c == '<' };
+            visible
+        })
+        .count()
+}
+
+/// Unbreakable pieces of a line: (space before it, text, width). Words
+/// are split by spaces; CJK characters are pieces of their own, with
+/// punctuation kept on the side it belongs to. `<...>` tags have no width.
+fn pieces(line: &str) -> Vec<(bool, String, usize)> {
+    let mut pieces: Vec<(bool, String, usize)> = Vec::new();
+    let mut space = false;
+    // the last piece takes more chars
+    let mut open = false;
+    let mut after_cjk = false;
+    let mut in_tag = false;
+    for c in line.chars() {
+        if in_tag || c == '<' {
+            in_tag = c != '>';
+            match pieces.last_mut() {
+                Some(piece) if open => piece.1.push(c),
+                _ => {
+                    pieces.push((space, c.to_string(), 0));
+                    space = false;
+                    open = true;
+                }
+            }
+            continue;
+        }
+        if c.is_whitespace() {
+            space = true;
+            open = false;
+            continue;
+        }
+        let cjk = is_cjk(c);
+        let last = pieces.last();
+        let join = open
+            && (NO_START.contains(c) || !cjk && !after_cjk || last.is_some_and(|p| p.2 == 0))
+            || last.is_some_and(|p| p.1.ends_with(|e| NO_END.contains(e)));
+        match pieces.last_mut() {
+            Some(piece) if join && !space => {
+                piece.1.push(c);
+                piece.2 += 1;
+            }
+            _ => pieces.push((space, c.to_string(), 1)),
+        }
+        space = false;
+        open = true;
+        after_cjk = cjk;
+    }
+    pieces
+}
+
+/// Re-wrap each line of text longer than `max` chars at spaces or
+/// between CJK characters, filling lines greedily. A word longer than
+/// `max` is left on its own line.
+pub(super) fn wrap(text: &str, max: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split("\r\n") {
+        if width(line) <= max {
+            lines.push(line.to_owned());
+            continue;
+        }
+        let mut current = String::new();
+        let mut used = 0;
+        for (space, piece, w) in pieces(line) {
+            let sep = if space && used > 0 { 1 } else { 0 };
+            if used > 0 && used + sep + w > max {
+                lines.push(current);
+                current = piece;
+                used = w;
+            } else {
+                if sep > 0 {
+                    current.push(' ');
+                }
+                current += &piece;
+                used += sep + w;
+            }
+        }
+        lines.push(current);
+    }
+    lines.join("\r\n")
+}
+
+/// Split dialogues of more than `max` lines into consecutive ones of
+/// `max` lines at most, each on screen for its share of the width.
+pub(super) fn split_lines(dialogues: Vec<Dialogue>, max: usize) -> Vec<Dialogue> {
+    let mut out = Vec::with_capacity(dialogues.len());
+    for d in dialogues {
+        let lines: Vec<&str> = d.text.split("\r\n").collect();
+        if d.comment || lines.len() <= max {
+            out.push(d);
+            continue;
+        }
+        let chunks: Vec<String> = lines.chunks(max).map(|c| c.join("\r\n")).collect();
+        let widths: Vec<u32> = lines
+            .chunks(max)
+            .map(|c| c.iter().map(|l| width(l)).sum::<usize>().max(1) as u32)
+            .collect();
+        let total: u32 = widths.iter().sum();
+        let duration = d.end.0 - d.start.0;
+        let mut done = 0;
+        let mut start = d.start;
+        for (text, w) in chunks.into_iter().zip(widths) {
+            done += w;
+            let end = Centisec(d.start.0 + duration * done / total);
+            out.push(Dialogue {
+                start,
+                end,
+                text: text.into(),
+                ..d.clone()
+            });
+            start = end;
+        }
+    }
+    out
+}
@@ -0,0 +1,136 @@
+use serde::Deserialize;
+
+/// How to write entry names in the zip.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum EntryNames {
+    Keep,
+    /// kana to romaji, then drop other non-ASCII; there is no pinyin
+    /// for Han characters, its table would bloat the wasm download
+    Romaji,
+    /// drop non-ASCII
+    Ascii,
+}
+
+/// Romaji of U+3041 (ぁ) to U+3096 (ゖ), "-" for small tsu.
+const KANA: [&str; 86] = [
+    "a", "a", "i", "i", "u", "u", "e", "e", "o", "o", "ka", "ga", "ki", "gi", "ku", "gu", "ke",
+    "ge", "ko", "go", "sa", "za", "shi", "ji", "su", "zu", "se", "ze", "so", "zo", "ta", "da",
+    "chi", "ji", "-", "tsu", "zu", "te", "de", "to", "do", "na", "ni", "nu", "ne", "no", "ha",
+    "ba", "pa", "hi", "bi", "pi", "fu", "bu", "pu", "he", "be", "pe", "ho", "bo", "po", "ma", "mi",
+    "mu", "me", "mo", "ya", "ya", "yu", "yu", "yo", "yo", "ra", "ri", "ru", "re", "ro", "wa", "wa",
+    "i", "e", "wo", "n", "vu", "ka", "ke",
+];
+
+fn romaji(c: char) -> Option<&'static str> {
+    let c = match c as u32 {
+        // katakana to hiragana
+        n @ 0x30a1..=0x30f6 => n - 0x60,
+        n => n,
+    };
+    match c {
+        0x3041..=0x3096 => Some(KANA[(c - 0x3041) as usize]),
+        _ => None,
+    }
+}
+
+/// Fullwidth ASCII (Ａ, １, etc.) to ASCII.
+fn halfwidth(c: char) -> char {
+    match c as u32 {
+        n @ 0xff01..=0xff5e => std::char::from_u32(n - 0xfee0).unwrap_or(c),
+        0x3000 => ' ',
+        _ => c,
+    }
+}
+
+/// Append romaji of one kana, joining small ya/yu/yo and small tsu
+/// with their neighbours: きゃ -> kya, しょ -> sho, がっこう -> gakkou.
+fn push_kana(out: &mut String, c: char, roma: &'static str, sokuon: &mut bool) {
+    let small_y = matches!(
+        c as u32,
+        0x3083 | 0x3085 | 0x3087 | 0x30e3 | 0x30e5 | 0x30e7
+    );
+    if roma == "-" {
+        *sokuon = true;
+        return;
+    }
+    if small_y && out.ends_with('i') {
+        out.pop();
+        // sh, ch, j drop the y
+        if out.ends_with("sh") || out.ends_with("ch") || out.ends_with('j') {
+            out.push_str(&roma[1..]);
+        } else {
+            out.push_str(roma);
+        }
+        return;
+    }
+    if *sokuon {
+        *sokuon = false;
+        if roma.starts_with("ch") {
+            out.push('t');
+        } else if !"aiueon".contains(&roma[..1]) {
+            out.push_str(&roma[..1]);
+        }
+    }
+    out.push_str(roma);
+}
+
+/// Rewrite an entry name for devices that mangle Unicode names.
+/// Dropped characters collapse into one "_"; a folder or file name
+/// left empty becomes "subtitle".
+pub fn transliterate(name: &str, mode: EntryNames) -> String {
+    if let EntryNames::Keep = mode {
+        return name.to_owned();
+    }
+    let romanize = matches!(mode, EntryNames::Romaji);
+    name.split('/')
+        .map(|segment| transliterate_segment(segment, romanize))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn transliterate_segment(name: &str, romanize: bool) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut sokuon = false;
+    for c in name.chars().map(halfwidth) {
+        match (c, romaji(c)) {
+            (c, _) if c.is_ascii() => out.push(c),
+            (c, Some(roma)) if romanize => push_kana(&mut out, c, roma, &mut sokuon),
+            // long vowel mark
+            ('ー', _) if romanize => (),
+            _ => {
+                if !out.ends_with('_') {
+                    out.push('_');
+                }
+            }
+        }
+    }
+    // trim "_" around the stem, keep the extension
+    let (stem, ext) = match out.rfind('.') {
+        Some(i) => out.split_at(i),
+        None => (out.as_str(), ""),
+    };
+    let stem = stem.trim_matches(|c| c == '_' || c == ' ');
+    let stem = if stem.is_empty() { "subtitle" } else { stem };
+    format!("{}{}", stem, ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transliterate() {
+        let romaji = |s| transliterate(s, EntryNames::Romaji);
+        assert_eq!(romaji("がっこう ep1.srt"), "gakkou ep1.srt");
+        assert_eq!(romaji("きゃしょ.ass"), "kyasho.ass");
+        assert_eq!(romaji("ラーメン.srt"), "ramen.srt");
+        assert_eq!(romaji("Ｓ０１ 中文字幕.srt"), "S01.srt");
+        assert_eq!(romaji("字幕/a.srt"), "subtitle/a.srt");
+        assert_eq!(romaji("fonts/字体.ttf"), "fonts/subtitle.ttf");
+
+        let ascii = |s| transliterate(s, EntryNames::Ascii);
+        assert_eq!(ascii("がっこう ep1.srt"), "ep1.srt");
+        assert_eq!(ascii("Season 01/第3集.zh.srt"), "Season 01/3_.zh.srt");
+        assert_eq!(transliterate("字幕.srt", EntryNames::Keep), "字幕.srt");
+    }
+}
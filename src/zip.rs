@@ -1,5 +1,9 @@
-use crc::{crc32, Hasher32};
-use std::io::{self, Read, Seek, SeekFrom, Write};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
 
 const LOCAL_FILE_HEADER_SIGNATURE: &'static [u8] = b"\x50\x4b\x03\x04";
 const CENTRAL_FILE_HEADER_SIGNATURE: &'static [u8] = b"\x50\x4b\x01\x02";
@@ -13,6 +17,18 @@ const INTERNAL_FILE_ATTRS: &'static [u8] = b"\x10\x00"; // text file
 const EXTERNAL_FILE_ATTRS: &'static [u8] = b"\x00\x00\x00\x00";
 const UNICODE_PATH_EXTRA_FIELD: &'static [u8] = b"\x75\x70";
 const UNICODE_PATH_VERSION: &'static [u8] = b"\x01";
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// What to do when an entry name is already taken.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Collision {
+    /// keep the later one
+    Overwrite,
+    /// keep the earlier one
+    Skip,
+    /// rename the later one to "name (2).ext"
+    Suffix,
+}
 
 pub struct ZipWriter<W> {
     writer: W,
@@ -49,8 +65,8 @@ impl<'a> Utf8PathField<'a> {
             .unwrap();
         buf.write(UNICODE_PATH_VERSION).unwrap();
 
-        let mut digest = crc32::Digest::new(crc32::IEEE);
-        digest.write(self.path.as_bytes());
+        let mut digest = Crc32::new();
+        digest.update(self.path.as_bytes());
         buf.write(&digest.sum32().to_le_bytes()).unwrap();
 
         buf.write(self.path.as_bytes()).unwrap();
@@ -122,7 +138,7 @@ where
         }
     }
 
-    pub fn write_file<R>(&mut self, filename: &str, content: R) -> io::Result<()>
+    pub fn write_file<R>(&mut self, filename: &str, mut content: R) -> io::Result<()>
     where
         R: Read,
     {
@@ -131,10 +147,22 @@ where
         let mut file = FileEntry::new(self.cursor, filename, 0, 0);
         self.cursor += file.write_header(&mut self.writer, FileHeader::Local)? as u64;
 
-        // write file content
-        let mut content = Crc32Reader::new(content);
-        file.size = io::copy(&mut content, &mut self.writer)?;
-        file.crc32 = content.sum32();
+        // write file content, hashing each chunk right after writing it
+        // while it is still in cache
+        let mut digest = Crc32::new();
+        let mut buf = vec![0; COPY_BUFFER_SIZE];
+        loop {
+            let len = match content.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.writer.write_all(&buf[..len])?;
+            digest.update(&buf[..len]);
+            file.size += len as u64;
+        }
+        file.crc32 = digest.sum32();
         self.cursor += file.size;
 
         // update header
@@ -171,28 +199,200 @@ where
     }
 }
 
-struct Crc32Reader<R> {
-    internal: R,
-    digest: crc32::Digest,
+lazy_static! {
+    /// Slicing-by-8 tables of CRC-32 (IEEE); `CRC_TABLES[0]` is the
+    /// classic byte-wise table.
+    static ref CRC_TABLES: [[u32; 256]; 8] = {
+        let mut tables = [[0u32; 256]; 8];
+        for (i, entry) in tables[0].iter_mut().enumerate() {
+            let mut crc = i as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 { crc >> 1 ^ 0xedb8_8320 } else { crc >> 1 };
+            }
+            *entry = crc;
+        }
+        for i in 0..256 {
+            for t in 1..8 {
+                let prev = tables[t - 1][i];
+                tables[t][i] = prev >> 8 ^ tables[0][(prev & 0xff) as usize];
+            }
+        }
+        tables
+    };
 }
 
-impl<R: Read> Crc32Reader<R> {
-    fn new(internal: R) -> Self {
-        Crc32Reader {
-            internal,
-            digest: crc32::Digest::new(crc32::IEEE),
+/// CRC-32 processing 8 bytes per step.
+struct Crc32 {
+    value: u32,
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        Crc32 { value: !0 }
+    }
+
+    fn update(&mut self, mut buf: &[u8]) {
+        let t = &*CRC_TABLES;
+        let mut crc = self.value;
+        while buf.len() >= 8 {
+            let lo = crc ^ u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+            crc = t[7][(lo & 0xff) as usize]
+                ^ t[6][(lo >> 8 & 0xff) as usize]
+                ^ t[5][(lo >> 16 & 0xff) as usize]
+                ^ t[4][(lo >> 24) as usize]
+                ^ t[3][buf[4] as usize]
+                ^ t[2][buf[5] as usize]
+                ^ t[1][buf[6] as usize]
+                ^ t[0][buf[7] as usize];
+            buf = &buf[8..];
         }
+        for &b in buf {
+            crc = crc >> 8 ^ t[0][((crc ^ u32::from(b)) & 0xff) as usize];
+        }
+        self.value = crc;
     }
 
     fn sum32(&self) -> u32 {
-        self.digest.sum32()
+        !self.value
+    }
+}
+
+fn suffixed(filename: &str, n: usize) -> String {
+    match filename.rfind('.') {
+        Some(i) => format!("{} ({}){}", &filename[..i], n, &filename[i..]),
+        None => format!("{} ({})", filename, n),
+    }
+}
+
+/// Apply collision policy on (filename, content) entries.
+/// Names are compared case-insensitively.
+pub fn resolve_names<T>(entries: Vec<(String, T)>, policy: Collision) -> Vec<(String, T)> {
+    let mut resolved: Vec<(String, T)> = Vec::with_capacity(entries.len());
+    let mut taken: HashMap<String, usize> = HashMap::new();
+    for (mut filename, content) in entries {
+        if let Some(&i) = taken.get(&filename.to_lowercase()) {
+            match policy {
+                Collision::Overwrite => {
+                    resolved[i].1 = content;
+                    continue;
+                }
+                Collision::Skip => continue,
+                Collision::Suffix => {
+                    filename = (2..)
+                        .map(|n| suffixed(&filename, n))
+                        .find(|f| !taken.contains_key(&f.to_lowercase()))
+                        .unwrap();
+                }
+            }
+        }
+        taken.insert(filename.to_lowercase(), resolved.len());
+        resolved.push((filename, content));
     }
+    resolved
 }
 
-impl<R: Read> Read for Crc32Reader<R> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let len = self.internal.read(buf)?;
-        self.digest.write(&buf[0..len]);
-        Ok(len)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<(String, u32)> {
+        ["a.srt", "b.srt", "A.srt", "a.srt", "c"]
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.to_string(), i as u32))
+            .collect()
+    }
+
+    fn resolve(policy: Collision) -> Vec<(String, u32)> {
+        resolve_names(entries(), policy)
+    }
+
+    #[test]
+    fn test_overwrite() {
+        assert_eq!(
+            resolve(Collision::Overwrite),
+            [("a.srt".into(), 3), ("b.srt".into(), 1), ("c".into(), 4)]
+        );
+    }
+
+    #[test]
+    fn test_skip() {
+        assert_eq!(
+            resolve(Collision::Skip),
+            [("a.srt".into(), 0), ("b.srt".into(), 1), ("c".into(), 4)]
+        );
+    }
+
+    #[test]
+    fn test_suffix() {
+        assert_eq!(
+            resolve(Collision::Suffix),
+            [
+                ("a.srt".into(), 0),
+                ("b.srt".into(), 1),
+                ("A (2).srt".into(), 2),
+                ("a (3).srt".into(), 3),
+                ("c".into(), 4)
+            ]
+        );
+        // a suffixed name already taken by an entry
+        let entries = vec![
+            ("a (2).srt".into(), 0),
+            ("a.srt".into(), 1),
+            ("a.srt".into(), 2),
+        ];
+        let names: Vec<_> = resolve_names(entries, Collision::Suffix)
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect();
+        assert_eq!(names, ["a (2).srt", "a.srt", "a (3).srt"]);
+        assert_eq!(suffixed("c", 2), "c (2)");
+    }
+
+    fn crc32(buf: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(buf);
+        crc.sum32()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414f_a339
+        );
+    }
+
+    #[test]
+    fn test_crc32_chunks() {
+        let buf: Vec<u8> = (0..100_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        // bit by bit, as the reference
+        let mut expected = !0u32;
+        for &b in &buf {
+            expected ^= u32::from(b);
+            for _ in 0..8 {
+                expected = if expected & 1 == 1 {
+                    expected >> 1 ^ 0xedb8_8320
+                } else {
+                    expected >> 1
+                };
+            }
+        }
+        assert_eq!(crc32(&buf), !expected);
+        let mut crc = Crc32::new();
+        let mut rest = &buf[..];
+        for size in [1, 7, 8, 9, 13, 4096, 3].iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, tail) = rest.split_at((*size).min(rest.len()));
+            crc.update(chunk);
+            rest = tail;
+        }
+        assert_eq!(crc.sum32(), crc32(&buf));
     }
 }
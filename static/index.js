@@ -1,5 +1,7 @@
 let $ = s => document.querySelector(s);
 let worker = new Worker("worker.js");
+// git hash filled in by deploy script
+const APP_BUILD = document.querySelector('meta[name="build"]').content;
 let nextId = 1;
 
 $("#files").addEventListener("change", async ev => {
@@ -15,19 +17,44 @@ $("#like").addEventListener("click", function(ev) {
   this.title = "Thanks!";
 });
 
+// user's dictionary on top of the selected one, sent once per change
+let convDictExtra = "";
 const preloadDict = (d) => worker.postMessage({
-  action: "preloadDict", dict: d,
+  action: "preloadDict", dict: d, extra: convDictExtra,
 });
 preloadDict($("#conv-dict").value);
 $("#conv-dict").addEventListener("change", ev =>
   preloadDict(ev.target.value));
+$("#conv-dict-extra").addEventListener("change", async ev => {
+  let file = ev.target.files[0];
+  convDictExtra = file ? await file.text() : "";
+  preloadDict($("#conv-dict").value);
+});
+
+let droppedTexts = 0;
+
+// text dragged from other pages or editors as a virtual file;
+// getAsString() must be called before the drop event returns
+const textAsFile = item => new Promise(resolve => item.getAsString(text => {
+  droppedTexts++;
+  resolve(new File([text], `dropped text ${droppedTexts}.ass`,
+    {type: "text/plain"}));
+}));
 
-function onDrop(ev) {
+async function onDrop(ev) {
   ev.preventDefault();
-  let items = ev.dataTransfer.items
+  let items = Array.from(ev.dataTransfer.items);
+  let files = items
     .filter(i => i.kind == "file")
     .map(f => f.getAsFile());
-  submit(items);
+  let text = items.find(i => i.kind == "string" && i.type == "text/plain");
+  if (files.length == 0 && text) {
+    let file = await textAsFile(text);
+    if (!/^\s*\[Events\]/m.test(await file.text()))
+      return addSkipped(file.name, "dropped text is not an ASS/SSA script");
+    files.push(file);
+  }
+  submit(files);
 };
 
 function onDropOver(ev) {
@@ -38,25 +65,266 @@ function onDropEnd(ev) {
   ev.dataTransfer.clearData();
 }
 
-function submit(files) {
-  if ($("#no-zip").checked)
+const loadTextInto = (input, textarea) =>
+  $(input).addEventListener("change", async ev => {
+    let file = ev.target.files.item(0);
+    if (file) $(textarea).value = await file.text();
+  });
+loadTextInto("#split-file", "#split-points");
+loadTextInto("#edl-file", "#edl");
+loadTextInto("#replace-table-file", "#replace-table");
+
+// every field under settings, for backup & restore
+const settingFields = () =>
+  Array.from(document.querySelectorAll("form details [id]"))
+    .filter(e => e.matches("input:not([type=file]), select, textarea"));
+
+$("#settings-export").addEventListener("click", ev => {
+  let settings = {};
+  for (let field of settingFields())
+    settings[field.id] = field.type == "checkbox" ? field.checked
+      : field.multiple ? Array.from(field.selectedOptions, o => o.value)
+      : field.value;
+  let json = JSON.stringify({ version: 1, settings: settings }, null, 2);
+  let blob = new Blob([json], {type: "application/json"});
+  URL.revokeObjectURL(ev.target.href);
+  ev.target.href = URL.createObjectURL(blob);
+});
+
+$("#settings-import").addEventListener("change", async ev => {
+  let file = ev.target.files.item(0);
+  if (!file) return;
+  let settings;
+  try {
+    settings = JSON.parse(await file.text()).settings;
+  } catch (e) {
+    return alert(`Not a settings backup: ${e}`);
+  }
+  for (let field of settingFields()) {
+    if (!(field.id in settings)) continue;
+    if (field.type == "checkbox") field.checked = settings[field.id];
+    else if (field.multiple)
+      for (let option of field.options)
+        option.selected = settings[field.id].includes(option.value);
+    else field.value = settings[field.id];
+    // e.g. preload Chinese dictionary
+    field.dispatchEvent(new Event("change"));
+  }
+  ev.target.value = "";
+});
+
+// split parts and fonts are always packed into zip
+const splitting = () => $("#split-points").value.trim() != "";
+const alsoFormats = () =>
+  Array.from($("#also-formats").selectedOptions, o => o.value);
+const zipped = () => splitting() || $("#extract-fonts").checked ||
+  $("#split-signs").checked ||
+  alsoFormats().length > 0;
+
+// every format can be also written next to the chosen one
+for (let option of $("#format").options)
+  $("#also-formats").add(new Option(option.text, option.value));
+
+// skip files with identical content, e.g. from overlapping folders
+async function dedupe(files) {
+  const sizes = files.map(f => f.size);
+  let seen = new Map();
+  let unique = [];
+  for (let f of files) {
+    // only hash when there is another file of the same size
+    if (sizes.indexOf(f.size) == sizes.lastIndexOf(f.size)) {
+      unique.push(f);
+      continue;
+    }
+    let digest = await crypto.subtle.digest("SHA-256", await f.arrayBuffer());
+    let hash = Array.from(new Uint8Array(digest)).join(",");
+    if (seen.has(hash)) {
+      addSkipped(f.name, `duplicate of ${seen.get(hash)}`);
+    } else {
+      seen.set(hash, f.name);
+      unique.push(f);
+    }
+  }
+  return unique;
+}
+
+const MiB = 1024 * 1024;
+// empty or zero for no limit
+const limit = input => parseFloat($(input).value) || Infinity;
+
+// guardrails against inputs that would freeze or crash the tab
+function checkLimits(files) {
+  let maxSize = limit("#max-file-size");
+  for (let f of files.filter(f => f.size > maxSize * MiB))
+    addSkipped(f.name, `larger than ${maxSize} MiB, see "Max file size"`);
+  files = files.filter(f => f.size <= maxSize * MiB);
+
+  let maxFiles = limit("#max-files");
+  if (files.length > maxFiles) {
+    addSkipped(`${files.length} files`,
+      `more than ${maxFiles} files at once, see "Max files"`);
+    return [];
+  }
+  let maxTotal = limit("#max-total-size");
+  let total = files.reduce((n, f) => n + f.size, 0);
+  if (total > maxTotal * MiB) {
+    addSkipped(`${files.length} files`,
+      `${(total / MiB).toFixed(1)} MiB in total, more than ${maxTotal} MiB, ` +
+      `see "Max total size"`);
+    return [];
+  }
+  return files;
+}
+
+async function submit(files) {
+  files = await dedupe(checkLimits(files));
+  if (files.length == 0) return;
+  if ($("#validate-only").checked) {
+    addFiles(files, "validate");
+  } else if ($("#compare").checked) {
+    addFiles(files, "compare");
+  } else if ($("#bilingual").checked) {
+    addFiles(files, "bilingual");
+  } else if ($("#concat").checked) {
+    // CD1, CD2, ..., CD10
+    files.sort((a, b) =>
+      a.name.localeCompare(b.name, undefined, { numeric: true }));
+    addFiles(files, "concat");
+  } else if ($("#merge-text").checked)
+    addFiles(files, "merge");
+  else if ($("#no-zip").checked)
     files.forEach(f => addFiles([f]));
   else
     addFiles(files);
 }
 
-function addFiles(files) {
+function addSkipped(name, reason) {
+  let id = nextId++;
+  let content = document.importNode($("#file"), true).content;
+  let item = content.querySelector(".file");
+  item.id = `file-${id}`;
+  item.classList.replace("progress", "skipped");
+  content.querySelector(".name").textContent = name;
+  content.querySelector(".status").textContent = reason;
+  content.querySelector(".close").addEventListener("click", event => {
+    event.preventDefault();
+    $(`#file-${id}`).outerHTML = "";
+  });
+  $("#list").appendChild(content);
+}
+
+// layer number, null if the field is blank
+const layer = value => value === "" ? null : parseInt(value);
+
+// options for the worker, from the form
+function readOptions() {
+  return {
+    in_charset: $("#in-charset").value || null,
+    out_charset: $("#out-charset").value || null,
+    lines: $("#lines").value,
+    ignore_codec_err: $("#ignore-codec-err").checked,
+    bom: $("#bom").checked,
+    line_ending: $("#line-ending").value,
+    offset_secs: parseFloat($("#offset").value) || 0,
+    rounding: $("#rounding").value,
+    fps_from: parseFloat($("#fps-from").value) || 0,
+    fps_to: parseFloat($("#fps-to").value) || 0,
+    snap_fps: parseFloat($("#snap-fps").value) || 0,
+    min_duration_secs: parseFloat($("#min-duration").value) || 0,
+    merge_duplicates: $("#merge-duplicates").checked,
+    max_chars_per_line: parseInt($("#max-chars").value) || 0,
+    max_lines: parseInt($("#max-lines").value) || 0,
+    hard_breaks: $("#hard-breaks").value,
+    soft_breaks: $("#soft-breaks").value,
+    edl: $("#edl").value,
+    retime: $("#retime").value,
+    trim_start_secs: parseFloat($("#trim-start").value) || null,
+    trim_end_secs: parseFloat($("#trim-end").value) || null,
+    trim_rebase: $("#trim-rebase").checked,
+    keep_order: $("#keep-order").checked,
+    check_untranslated: $("#check-untranslated").checked,
+    check_coverage: $("#check-coverage").checked,
+    max_cps: parseFloat($("#max-cps").value) || 0,
+    timeline: $("#timeline").checked,
+    style_colors: $("#style-colors").value,
+    include_styles: $("#include-styles").value,
+    exclude_styles: $("#exclude-styles").value,
+    include_actors: $("#include-actors").value,
+    exclude_actors: $("#exclude-actors").value,
+    min_layer: layer($("#min-layer").value),
+    max_layer: layer($("#max-layer").value),
+    format: $("#format").value,
+    also_formats: alsoFormats(),
+    fps: parseFloat($("#fps").value) || 0,
+    vtt_styles: $("#vtt-styles").checked,
+    vtt_classes: $("#vtt-classes").checked,
+    vtt_regions: $("#vtt-regions").checked,
+    keep_basic_tags: $("#keep-basic-tags").checked,
+    keep_colors: $("#keep-colors").checked,
+    speaker_sep: $("#speakers").checked ? $("#speaker-sep").value : null,
+    speaker_upper: $("#speaker-upper").checked,
+    lrc_words: $("#lrc-words").checked,
+    karaoke_lines: $("#karaoke-lines").checked,
+    transcript_actors: $("#transcript-actors").checked,
+    comments: $("#comments").checked,
+    comment_cues: $("#comment-cues").checked,
+    drop_signs: $("#drop-signs").checked,
+    strip_sdh: $("#strip-sdh").checked,
+    split_signs: $("#split-signs").checked,
+    forced: $("#forced").checked,
+    effects: $("#effects").value,
+    bidi: $("#bidi").value,
+    chapter_style: $("#chapter-style").value,
+    chapter_regex: $("#chapter-regex").value,
+    chapter_comments: $("#chapter-comments").checked,
+    chapter_format: $("#chapter-format").value,
+    extract_fonts: $("#extract-fonts").checked,
+    split_points: $("#split-points").value,
+    collision: $("#collision").value,
+    entry_names: $("#entry-names").value,
+    series_folders: $("#series-folders").checked,
+    concat_offsets: $("#concat-offsets").value,
+    censor_langs: $("#censor").value.split(",").filter(l => l),
+    censor_words: $("#censor-words").value,
+    censor_mask: $("#censor-mask").value,
+    ocr_langs: $("#ocr").value.split(",").filter(l => l),
+    replace_rules: $("#replace-rules").value,
+    replace_table: $("#replace-table").value,
+  };
+}
+
+// mode: "concat" to join files into one SRT, "merge" for one text with
+// all outputs, "compare" for diff of two files, "bilingual" for two files
+// in one SRT, "validate" for checking only; otherwise one SRT per file.
+function addFiles(files, mode = null) {
   let id = nextId++;
   let template = document.querySelector("#file");
   let content = document.importNode(template, true).content;
   content.querySelector(".file").id = `file-${id}`;
-  if (files.length == 1) {
+  if (mode == "validate") {
+    content.querySelector(".name").textContent =
+      `${files.length} subtitle file(s) checked`;
+  } else if (mode == "compare") {
+    content.querySelector(".file").classList.add("text");
+    content.querySelector(".name").textContent =
+      files.map(f => f.name).join(" ⇄ ");
+    content.querySelector(".save").download = `subtitles_diff.txt`;
+  } else if (mode == "merge") {
+    content.querySelector(".file").classList.add("text");
+    content.querySelector(".name").textContent =
+      `${files.length} subtitle files in text`;
+    content.querySelector(".save").download = `srt_subtitles.txt`;
+  } else if (files.length == 1 || mode == "concat" || mode == "bilingual") {
     let name = files[0].name;
-    if (name.match(/\.(ass|ssa)$/) != null)
-      name = name.slice(0, -4);
-    name += '.srt'
-    content.querySelector(".name").textContent = name;
-    content.querySelector(".save").download = name;
+    name = name.replace(/\.(ass|ssa|srt|vtt)$/i, "");
+    let ext = { WebVtt: '.vtt', MicroDvd: '.sub', Lrc: '.lrc',
+      Ttml: '.ttml', Sbv: '.sbv',
+      Sami: '.smi', Transcript: '.txt',
+      Json: '.json', Csv: '.csv',
+      Ass: '.ass', Labels: '.labels.txt' }[$("#format").value] || '.srt';
+    content.querySelector(".name").textContent = name + ext;
+    content.querySelector(".save").download =
+      name + (zipped() && !mode ? '.zip' : ext);
   } else {
     content.querySelector(".name").textContent =
       `${files.length} subtitle files`;
@@ -66,21 +334,36 @@ function addFiles(files) {
     event.preventDefault();
     $(`#file-${id}`).outerHTML = "";
   });
+  content.querySelector(".copy").addEventListener("click", async event => {
+    event.preventDefault();
+    let url = $(`#file-${id} .save`).href;
+    await navigator.clipboard.writeText(await (await fetch(url)).text());
+    event.target.textContent = "✔ COPIED";
+  });
   $("#list").appendChild(content);
 
-  let size = files.reduce((n, f) => n + f.size, 0);
-  if (size > 100 * 1024 * 1024)
-    return onConvertError(id, "files too large (> 100 MiB)");
+  if (mode == "compare" && files.length != 2)
+    return onConvertError(id, "select exactly two files to compare");
+  if (mode == "bilingual" && files.length != 2)
+    return onConvertError(id, "select exactly two files to merge");
 
-  let opts = {
-    in_charset: $("#in-charset").value || null,
-    out_charset: $("#out-charset").value || null,
-    lines: $("#lines").value,
-    ignore_codec_err: $("#ignore-codec-err").checked,
-    offset_secs: parseFloat($("#offset").value) || 0,
-  };
-  let cmd = { id: id, opts: opts };
-  if (files.length == 1) {
+  let cmd = { id: id, opts: readOptions() };
+  if (mode == "concat") {
+    cmd.action = "concatFiles";
+    cmd.files = files;
+  } else if (mode == "compare") {
+    cmd.action = "compareFiles";
+    cmd.files = files;
+  } else if (mode == "bilingual") {
+    cmd.action = "bilingualFiles";
+    cmd.files = files;
+  } else if (mode == "validate") {
+    cmd.action = "validateFiles";
+    cmd.files = files;
+  } else if (mode == "merge") {
+    cmd.action = "mergeFiles";
+    cmd.files = files;
+  } else if (files.length == 1 && !zipped()) {
     cmd.action = "addFile";
     cmd.file = files[0];
   } else {
@@ -90,29 +373,194 @@ function addFiles(files) {
   worker.postMessage(cmd);
 }
 
-function onConvertError(id, msg) {
+function onConvertError(id, msg, version = null) {
   let content = $(`#file-${id}`);
   content.classList.remove("progress");
   content.classList.add("error");
   content.querySelector(".status").textContent = msg;
+  if (version)
+    content.querySelector(".status").title =
+      `converter ${version}, app ${APP_BUILD}`;
+}
+
+// seconds to "h:mm:ss"
+const hms = secs => [secs / 3600, secs / 60 % 60, secs % 60]
+  .map(n => Math.floor(n).toString().padStart(2, "0"))
+  .join(":").replace(/^0/, "");
+
+// warning category: text before the first "?" or ":"
+const category = warning => warning.split(/[?:]/)[0].trim();
+
+// totals, script breakdown and warnings per category of a batch
+function summarize(stats) {
+  let count = (keys) => {
+    let counts = new Map();
+    keys.forEach(k => counts.set(k, (counts.get(k) || 0) + 1));
+    return Array.from(counts, ([k, n]) => `${k} ${n}`).join(", ");
+  };
+  let dialogues = stats.reduce((n, s) => n + s.dialogues, 0);
+  let duration = stats.reduce((n, s) => n + s.duration, 0);
+  let lines = [
+    `${stats.length} files, ${dialogues} dialogues, ` +
+      `${hms(duration)} of subtitles`,
+    "Scripts: " + (count(stats.map(s => s.script || "unknown")) || "none"),
+    "Charsets: " + (count(stats.map(s => s.charset || "unknown")) || "none"),
+    "Forced: " + stats.filter(s => s.forced).length,
+    "Drawings skipped: " + stats.reduce((n, s) => n + s.drawings, 0),
+    "Cues too fast to read: " + stats.reduce((n, s) => n + s.fast_cues, 0),
+    "Characters lost in decoding: " +
+      stats.reduce((n, s) => n + s.decode_errors, 0),
+    "Characters lost in encoding: " +
+      stats.reduce((n, s) => n + s.lossy_chars, 0),
+    "Unicode blocks: " + (count([].concat(...stats.map(s => s.blocks))) ||
+      "not checked"),
+    "Warnings: " + (count([].concat(...stats.map(s =>
+      s.warnings.map(category)))) || "none"),
+  ];
+  const field = v => `"${String(v).replace(/"/g, '""')}"`;
+  let csv = [["file", "dialogues", "duration_secs", "script", "forced",
+    "charset", "blocks", "drawings", "fast_cues", "max_cps", "decode_errors",
+    "lossy_chars", "warnings"]]
+    .concat(stats.map(s => [s.name, s.dialogues, s.duration.toFixed(2),
+      s.script || "", s.forced ? "yes" : "no", s.charset || "",
+      s.blocks.join("; "),
+      s.drawings, s.fast_cues, s.max_cps.toFixed(1), s.decode_errors, s.lossy_chars,
+      s.warnings.length]))
+    .map(row => row.map(field).join(","))
+    .join("\r\n") + "\r\n";
+  return { lines: lines, csv: csv };
+}
+
+// gaps at least this long are marked on timeline
+const GAP_SECS = 10;
+
+// bars of cues at their times, zoomed by the range input
+function drawTimeline(content, cues) {
+  content.classList.add("timelined");
+  let track = content.querySelector(".timeline .track");
+  let total = cues.reduce((t, c) => Math.max(t, c.end), 1);
+  const pct = t => `${t / total * 100}%`;
+  const bar = (start, end, className, title) => {
+    let span = document.createElement("span");
+    span.className = className;
+    span.style.left = pct(start);
+    span.style.width = pct(end - start);
+    span.title = title;
+    track.appendChild(span);
+  };
+  let lastEnd = 0;
+  for (let c of cues) {
+    if (c.start - lastEnd >= GAP_SECS)
+      bar(lastEnd, c.start, "gap", `gap of ${hms(c.start - lastEnd)}`);
+    lastEnd = Math.max(lastEnd, c.end);
+    let className = "cue" + (c.overlap ? " overlap" : "") +
+      (c.short ? " short" : "");
+    bar(c.start, c.end, className, `${hms(c.start)} – ${hms(c.end)}`);
+  }
+  content.querySelector(".timeline input").addEventListener("input", ev =>
+    track.style.width = `${ev.target.value * 100}%`);
 }
 
-function onConvertDone(id, url) {
+function onConvertDone(id, url, status, warnings = [], chapters = null,
+  stats = [], timeline = null) {
   let content = $(`#file-${id}`);
   content.classList.remove("progress");
   content.classList.add("done");
-  content.querySelector(".save").href = url;
-  content.querySelector(".close").addEventListener("click", event => {
-    URL.revokeObjectURL(url);
-  });
+  if (status) content.querySelector(".status").textContent = status;
+  if (warnings.length > 0) {
+    content.classList.add("warned");
+    content.querySelector(".warnings summary").textContent =
+      `${warnings.length} warning(s)`;
+    let list = content.querySelector(".warnings ul");
+    for (let w of warnings) {
+      let item = document.createElement("li");
+      item.textContent = w;
+      list.appendChild(item);
+    }
+  }
+  if (!url) {
+    // validate only
+    content.classList.add("nosave");
+  } else {
+    content.querySelector(".save").href = url;
+    content.querySelector(".close").addEventListener("click", event => {
+      URL.revokeObjectURL(url);
+    });
+  }
+  if (stats.length == 1 && stats[0].forced) {
+    // "movie.zh.srt" -> "movie.zh.forced.srt"
+    let save = content.querySelector(".save");
+    if (!save.download.endsWith(".zip"))
+      save.download = save.download.replace(/(\.\w+)$/, ".forced$1");
+    content.querySelector(".name").textContent += " (forced)";
+  }
+  if (stats.length > 1) {
+    let summary = summarize(stats);
+    content.classList.add("summarized");
+    let list = content.querySelector(".stats ul");
+    for (let line of summary.lines) {
+      let item = document.createElement("li");
+      item.textContent = line;
+      list.appendChild(item);
+    }
+    let blob = new Blob([summary.csv], {type: "text/csv"});
+    content.querySelector(".stats a").href = URL.createObjectURL(blob);
+  }
+  if (chapters) {
+    content.classList.add("chaptered");
+    let blob = new Blob([chapters], {type: "text/plain"});
+    content.querySelector(".chapters").href = URL.createObjectURL(blob);
+  }
+  if (timeline) drawTimeline(content, timeline);
   $('#vote').style.display = 'block';
 }
 
 worker.onmessage = function(e) {
   let result = e.data;
-  if (result.error) {
-    onConvertError(result.id, result.error);
+  if (result.id == "selftest") {
+    return;
+  } else if (result.id == "version") {
+    $("#about .worker").textContent = result.version;
+  } else if (result.error) {
+    onConvertError(result.id, result.error, result.version);
   } else {
-    onConvertDone(result.id, result.url);
+    onConvertDone(result.id, result.url, result.status, result.warnings,
+      result.chapters, result.stats, result.timeline);
   }
 }
+
+$("#about .app").textContent = APP_BUILD;
+worker.postMessage({ action: "version", id: "version" });
+
+// "?selftest": run a built-in file through the whole pipeline and report
+// each stage, for bug reports
+async function selfTest() {
+  $("#selftest").hidden = false;
+  let report = (stage, ok, detail = "") => {
+    let item = document.createElement("li");
+    item.className = ok ? "ok" : "failed";
+    item.textContent = `${ok ? "✔" : "✘"} ${stage}` +
+      (detail ? `: ${detail}` : "");
+    $("#selftest ul").appendChild(item);
+  };
+  report("user agent", true, navigator.userAgent);
+  let started = performance.now();
+  let spawned = false;
+  let timer = setTimeout(() => spawned ||
+    report("worker spawn", false, "no response in 10s"), 10000);
+  worker.addEventListener("error", e =>
+    report("worker", false, e.message));
+  worker.addEventListener("message", e => {
+    if (e.data.id != "selftest") return;
+    if (!spawned) {
+      spawned = true;
+      clearTimeout(timer);
+      let ms = Math.round(performance.now() - started);
+      report("worker spawn", true, `${ms} ms`);
+    }
+    if (e.data.stage) report(e.data.stage, e.data.ok, e.data.detail);
+  });
+  worker.postMessage({ action: "selfTest", id: "selftest", opts: readOptions() });
+}
+if (new URLSearchParams(location.search).has("selftest"))
+  selfTest();
@@ -9,8 +9,22 @@ onmessage = async ev => {
     await addFile(ev.data.id, ev.data.file, ev.data.opts);
   else if (ev.data.action == "addFiles")
     await addFiles(ev.data.id, ev.data.files, ev.data.opts);
+  else if (ev.data.action == "concatFiles")
+    await concatFiles(ev.data.id, ev.data.files, ev.data.opts);
+  else if (ev.data.action == "bilingualFiles")
+    await bilingualFiles(ev.data.id, ev.data.files, ev.data.opts);
+  else if (ev.data.action == "mergeFiles")
+    await mergeFiles(ev.data.id, ev.data.files, ev.data.opts);
+  else if (ev.data.action == "validateFiles")
+    await validateFiles(ev.data.id, ev.data.files, ev.data.opts);
+  else if (ev.data.action == "compareFiles")
+    await compareFiles(ev.data.id, ev.data.files, ev.data.opts);
+  else if (ev.data.action == "selfTest")
+    await selfTest(ev.data.id, ev.data.opts);
+  else if (ev.data.action == "version")
+    postMessage({id: ev.data.id, version: await buildVersion()});
   else if (ev.data.action == "preloadDict")
-    preloadDict(ev.data.dict);
+    preloadDict(ev.data.dict, ev.data.extra);
   else
     throw "unknown action " + ev.data.action;
 };
@@ -21,10 +35,12 @@ async function addFile(id, file, opts) {
     let wasm = await Rust.asstosrt_wasm;
     let ass = reader.readAsArrayBuffer(file);
     let srt = wasm.assToSrt(ass, opts);
-    let url = URL.createObjectURL(srt);
-    postMessage({id: id, url: url});
+    let timeline = opts.timeline ? wasm.timeline(ass, opts) : null;
+    let url = URL.createObjectURL(srt.blob);
+    postMessage({id: id, url: url, warnings: srt.warnings,
+      chapters: srt.chapters, stats: srt.stats, timeline: timeline});
   } catch (e) {
-    postMessage({id: id, error: e});
+    postError(id, e);
   }
 }
 
@@ -32,24 +48,143 @@ async function addFiles(id, files, opts) {
   try {
     opts.conv_dict = await conv_dict;
     let wasm = await Rust.asstosrt_wasm;
-    let names = files.map(f => renameToSrt(f.name));
+    let names = files.map(f => renameToSrt(f.name, opts.format));
     let contents = files.map(f => reader.readAsArrayBuffer(f));
     let zip = wasm.assToSrtBulk(contents, names, opts);
-    let url = URL.createObjectURL(zip);
-    postMessage({id: id, url: url});
+    let url = URL.createObjectURL(zip.blob);
+    postMessage({id: id, url: url, warnings: zip.warnings, stats: zip.stats});
   } catch (e) {
-    postMessage({id: id, error: e});
+    postError(id, e);
   }
 }
 
-function preloadDict(dict) {
-  conv_dict = dict ? fetchChineseConvDict(dict) : null;
+async function concatFiles(id, files, opts) {
+  try {
+    opts.conv_dict = await conv_dict;
+    let wasm = await Rust.asstosrt_wasm;
+    let contents = files.map(f => reader.readAsArrayBuffer(f));
+    let srt = wasm.assToSrtConcat(contents, opts);
+    let url = URL.createObjectURL(srt.blob);
+    postMessage({id: id, url: url, warnings: srt.warnings,
+      chapters: srt.chapters, stats: srt.stats});
+  } catch (e) {
+    postError(id, e);
+  }
+}
+
+async function bilingualFiles(id, files, opts) {
+  try {
+    opts.conv_dict = await conv_dict;
+    let wasm = await Rust.asstosrt_wasm;
+    let contents = files.map(f => reader.readAsArrayBuffer(f));
+    let srt = wasm.assToSrtBilingual(contents, opts);
+    let url = URL.createObjectURL(srt.blob);
+    postMessage({id: id, url: url, warnings: srt.warnings,
+      chapters: srt.chapters, stats: srt.stats});
+  } catch (e) {
+    postError(id, e);
+  }
 }
 
-function renameToSrt(path) {
-  if (path.endsWith('.ass'))
-    path = path.slice(0, -4);
-  return path + ".srt";
+async function mergeFiles(id, files, opts) {
+  try {
+    opts.conv_dict = await conv_dict;
+    let wasm = await Rust.asstosrt_wasm;
+    let names = files.map(f => renameToSrt(f.name, opts.format));
+    let contents = files.map(f => reader.readAsArrayBuffer(f));
+    let text = wasm.assToSrtMerged(contents, names, opts);
+    let url = URL.createObjectURL(text.blob);
+    postMessage({id: id, url: url, warnings: text.warnings,
+      stats: text.stats});
+  } catch (e) {
+    postError(id, e);
+  }
+}
+
+// convert and discard, report errors & warnings of each file
+async function validateFiles(id, files, opts) {
+  try {
+    opts.conv_dict = await conv_dict;
+    let wasm = await Rust.asstosrt_wasm;
+    let failed = 0;
+    let dialogues = 0;
+    let warnings = [];
+    let stats = [];
+    for (let f of files) {
+      try {
+        let result = wasm.validateSubtitle(reader.readAsArrayBuffer(f), opts);
+        dialogues += result.dialogues;
+        stats.push(...result.stats.map(s => Object.assign(s, {name: f.name})));
+        warnings.push(...result.warnings.map(w => `${f.name}: ${w}`));
+      } catch (e) {
+        failed++;
+        warnings.push(`${f.name}: error: ${e}`);
+      }
+    }
+    let status = `${files.length - failed} ok, ${failed} failed, ` +
+      `${dialogues} dialogues`;
+    postMessage({id: id, status: status, warnings: warnings, stats: stats});
+  } catch (e) {
+    postError(id, e);
+  }
+}
+
+async function compareFiles(id, files, opts) {
+  try {
+    opts.conv_dict = await conv_dict;
+    let wasm = await Rust.asstosrt_wasm;
+    let [a, b] = files.map(f => reader.readAsArrayBuffer(f));
+    let diff = wasm.compareSubtitles(a, b, opts);
+    let url = URL.createObjectURL(diff.blob);
+    postMessage({id: id, url: url, status: diff.summary});
+  } catch (e) {
+    postError(id, e);
+  }
+}
+
+// "0.1.0+abc1234 (release)" of the wasm build
+async function buildVersion() {
+  try {
+    let v = (await Rust.asstosrt_wasm).version();
+    return `${v.version}${v.commit ? "+" + v.commit : ""} (${v.profile})`;
+  } catch (e) {
+    return "unknown";
+  }
+}
+
+// error with build version, for bug reports
+async function postError(id, e) {
+  postMessage({id: id, error: e, version: await buildVersion()});
+}
+
+// cached for every file until the selection changes
+function preloadDict(dict, extra) {
+  conv_dict = dict || extra ? loadConvDict(dict, extra) : null;
+}
+
+async function loadConvDict(dict, extra) {
+  let base = dict ? await fetchChineseConvDict(dict) : "";
+  return extra ? mergeDict(base, extra) : base;
+}
+
+// user's "from<TAB>to" entries replace built-in ones of the same key
+function mergeDict(base, extra) {
+  let entries = new Map();
+  for (let text of [base, extra])
+    for (let line of text.split(/\r?\n/)) {
+      let tab = line.indexOf("\t");
+      if (tab > 0) entries.set(line.slice(0, tab), line);
+    }
+  return Array.from(entries.values()).join("\n");
+}
+
+function renameToSrt(path, format) {
+  path = path.replace(/\.(ass|ssa|srt|vtt)$/i, "");
+  return path + ({ WebVtt: ".vtt", MicroDvd: ".sub", Lrc: ".lrc",
+    Ttml: ".ttml", Sbv: ".sbv",
+    Sami: ".smi", Transcript: ".txt",
+    Json: ".json", Csv: ".csv",
+    Ass: ".ass", Labels: ".labels.txt" }[format] || ".srt");
 }
 
 async function fetchChineseConvDict(dict) {
@@ -59,3 +194,58 @@ async function fetchChineseConvDict(dict) {
   return await resp.text();
 }
 
+
+const SELFTEST_ASS = `[Script Info]
+ScriptType: v4.00+
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\b1}自检{\\b0} self-test
+`;
+const SELFTEST_SRT = "1\r\n00:00:01,000 --> 00:00:02,500\r\n自检 self-test\r\n\r\n";
+
+async function selfTest(id, opts) {
+  const stage = (name, ok, detail) =>
+    postMessage({id: id, stage: name, ok: ok, detail: detail});
+  const run = async (name, f) => {
+    try {
+      let detail = await f();
+      stage(name, true, detail);
+      return true;
+    } catch (e) {
+      stage(name, false, String(e));
+      return false;
+    }
+  };
+  // worker is up
+  postMessage({id: id});
+  let wasm;
+  let ok = await run("wasm init", async () => {
+    wasm = await Rust.asstosrt_wasm;
+  });
+  if (!ok) return;
+  opts = Object.assign(opts, {
+    in_charset: "utf-8", out_charset: "utf-8", conv_dict: null, lines: "All",
+    format: "Srt", fps: 0, split_points: "", offset_secs: 0, edl: "", retime: "",
+    trim_start_secs: null, trim_end_secs: null, censor_langs: [], ocr_langs: [],
+    censor_words: "", style_colors: "", extract_fonts: false,
+  });
+  let ass = new TextEncoder().encode(SELFTEST_ASS).buffer;
+  const srtOf = blob => reader.readAsText(blob);
+  await run("conversion", () => {
+    let srt = srtOf(wasm.assToSrt(ass, opts).blob);
+    if (srt != SELFTEST_SRT) throw `unexpected output ${JSON.stringify(srt)}`;
+  });
+  await run("charset detection", () => {
+    let srt = srtOf(wasm.assToSrt(ass, Object.assign({}, opts, {
+      in_charset: null,
+    })).blob);
+    if (srt != SELFTEST_SRT) throw `wrong charset, got ${JSON.stringify(srt)}`;
+  });
+  await run("archive", () => {
+    let zip = wasm.assToSrtBulk([ass], ["selftest.srt"], opts).blob;
+    let head = new Uint8Array(reader.readAsArrayBuffer(zip));
+    if (head[0] != 0x50 || head[1] != 0x4b) throw "not a zip file";
+    return `${zip.size} bytes`;
+  });
+}